@@ -1,68 +1,58 @@
+mod contrast;
+mod cvd;
 mod oklab;
+mod palette;
+mod progress;
 mod rgb;
-use parking_lot::Mutex;
-//use peroxide::fuga::*;
-use rayon::prelude::*;
+mod stats;
+use palette::lightness_band::{self, HueRange};
 
 const MARGIN: f64 = 0.01;
 pub fn main() {
     let start_time = std::time::Instant::now();
 
-    let saved_saturation = Mutex::new(f64::NEG_INFINITY);
-    let saved_color = Mutex::new(rgb::sRGB { r: 0, g: 0, b: 0 });
-
-    rgb::sRGB::all_colors().par_bridge().for_each(|color| {
-        let oklch_color = color.to_oklch();
-
-        let saturation = oklch_color.c / (oklch_color.c + oklch_color.l);
-
-        let mut locked_saved_saturation = saved_saturation.lock();
-        let mut locked_saved_color = saved_color.lock();
-
-        if saturation >= *locked_saved_saturation
-            && oklch_color.l <= f64::from_bits(0x3fe48d9b6500e14b) + MARGIN
-            && oklch_color.l >= f64::from_bits(0x3fe48d9b6500e14b) - MARGIN
-        {
-            *locked_saved_saturation = saturation;
-            *locked_saved_color = color;
-        }
-    });
-
-    let saved_saturation = saved_saturation.into_inner();
-    let saved_color = saved_color.into_inner();
+    let all_l = stats::Distribution::cube(stats::Channel::Lightness);
+    let target_l = all_l.median().expect("The sRGB cube isn't empty");
+    let saturation = oklab::SaturationModel::ChromaPerChromaPlusLightness;
+
+    let (saved_color, saved_saturation) = lightness_band::most_saturated_at_lightness(
+        target_l,
+        MARGIN,
+        saturation,
+        HueRange {
+            buckets: 1,
+            ..HueRange::default()
+        },
+    )[0]
+    .expect("No color is close enough to the target lightness");
 
     dbg!(saved_saturation);
     dbg!(saved_color);
     dbg!(saved_color.to_oklch());
 
-    /*
-    let all_l = rgb::sRGB::all_colors()
-        .par_bridge()
-        .map(|color| color.to_oklab().l)
-        .collect::<Vec<f64>>();
-
-    println!("{:#}", all_l.quantile(1.0 / 3.0, Type2));
-    println!("{:#x}", all_l.quantile(1.0 / 3.0, Type2).to_bits());
-    println!("{:#}", all_l.quantile(0.5, Type2));
-    println!("{:#x}", all_l.quantile(0.5, Type2).to_bits());
-    println!("{:#}", all_l.quantile(2.0 / 3.0, Type2));
-    println!("{:#x}", all_l.quantile(2.0 / 3.0, Type2).to_bits());
-
-    let all_saturations = rgb::sRGB::all_colors()
-        .par_bridge()
-        .map(|color| {
-            let oklch_color = color.to_oklch();
-            let saturation = oklch_color.c / (oklch_color.c + oklch_color.l);
-            if saturation.is_finite() {
-                saturation
-            } else {
-                return 0.0;
-            }
-        })
-        .collect::<Vec<f64>>();
-
-    println!("{:#}", all_saturations.quantile(1.0 / 2.0, Type2));
-    */
+    // The same, for every hue at once
+    let hue_range = HueRange::default();
+    let per_hue =
+        lightness_band::most_saturated_at_lightness(target_l, MARGIN, saturation, hue_range);
+    for (index, best) in per_hue.into_iter().enumerate() {
+        let (start, end) = hue_range.bounds(index);
+        match best {
+            Some((color, saturation)) => println!("{start:.0} to {end:.0}: {color}, {saturation}"),
+            None => println!("{start:.0} to {end:.0}: none"),
+        }
+    }
+
+    for (name, p) in [("1/3", 1.0 / 3.0), ("median", 0.5), ("2/3", 2.0 / 3.0)] {
+        let quantile = all_l.quantile(p).expect("The sRGB cube isn't empty");
+        println!("{name} l:\n{quantile:#}\nf64::from_bits({:#x})", quantile.to_bits());
+    }
+
+    let all_saturations = stats::Distribution::cube(stats::Channel::Saturation(saturation));
+    let median_saturation = all_saturations.median().expect("The sRGB cube isn't empty");
+    println!(
+        "median saturation:\n{median_saturation:#}\nf64::from_bits({:#x})",
+        median_saturation.to_bits()
+    );
 
     let end_time = start_time.elapsed();
     dbg!(end_time);
@@ -1,5 +1,7 @@
+mod oklab;
+mod rgb;
+use crate::oklab::SaturationModel;
 use crate::rgb::*;
-use parking_lot::Mutex;
 use rand::prelude::*;
 use rayon::prelude::*;
 
@@ -7,38 +9,51 @@ pub fn main() {
     // Time for benchmarking purposes
     let start_time = std::time::SystemTime::now();
 
-    let saved_color = Mutex::new(sRGB::default());
-    let saved_delta = Mutex::new(f64::NEG_INFINITY);
+    // Pass `--seed <n>` to reproduce an earlier run, otherwise a random seed is picked and printed
+    let seed = std::env::args()
+        .skip_while(|argument| argument != "--seed")
+        .nth(1)
+        .map_or_else(rand::random, |seed| {
+            seed.parse().expect("Seed must be an unsigned integer")
+        });
+    println!("Seed: {seed}");
+
+    let mut saved_color = sRGB::default();
+    let mut saved_delta = f64::NEG_INFINITY;
 
     //let mut limits = (0..=255).map(|x| sRGB { r: x, g: x, b: x }).collect::<Vec<_>>();
     let mut limits = sRGB::all_colors().collect::<Vec<_>>();
-    let mut rng = rand::thread_rng();
+    let mut rng = StdRng::seed_from_u64(seed);
     limits.shuffle(&mut rng);
 
     let oklab_colors = limits.par_iter().map(|srgb_color| srgb_color.to_oklab());
 
-    oklab_colors.clone().for_each(|original| {
-        /*
-        let max_delta = oklab_colors
-            .clone()
-            .map(|sample| original.delta_E_Hyab(sample))
-            .reduce(|| f64::NEG_INFINITY, |a, b| a.max(b));
-        */
-        let max_delta = original.saturation();
-
-        let mut locked_saved_color = saved_color.lock();
-        let mut locked_saved_delta = saved_delta.lock();
+    // Scored in parallel, but scanned in shuffled order so that "New best" lines are the same for the same seed
+    let scored_colors = oklab_colors
+        .clone()
+        .map(|original| {
+            /*
+            let max_delta = oklab_colors
+                .clone()
+                .map(|sample| original.delta_E_Hyab(sample))
+                .reduce(|| f64::NEG_INFINITY, |a, b| a.max(b));
+            */
+            let max_delta = original.saturation(SaturationModel::ChromaPerDistanceFromBlack);
+            (original, max_delta)
+        })
+        .collect::<Vec<_>>();
 
-        if max_delta > *locked_saved_delta {
-            *locked_saved_delta = max_delta;
-            *locked_saved_color = original.to_srgb();
+    for (original, max_delta) in scored_colors {
+        if max_delta > saved_delta {
+            saved_delta = max_delta;
+            saved_color = original.to_srgb();
             println!(
                 "New best: {} / {}, {max_delta}",
                 original.to_srgb(),
                 original.to_oklch()
             );
         }
-    });
+    }
 
     println!("saved_color: {saved_color:?}");
     println!("saved_delta: {saved_delta:?}");
@@ -66,7 +81,7 @@ New best: sRGB(118, 121, 108) / Oklch(0.5695902238581334, 0.01980537412749601, 2
 error: process didn't exit successfully: `target\release\contrasting_colors.exe` (exit code: 0xc000013a, STATUS_CONTROL_C_EXIT)
 ^C
 
-most saturated color (both .chroma()/.l and .saturation())
+most saturated color (both ChromaPerLightness and ChromaPerDistanceFromBlack)
 unreferenced white:
 New best: sRGB(0, 0, 172) / Oklch(0.33649185331733306, 0.23316567643904332, -1.6746081505015074), 0.5695563289431641
 saved_color: Mutex { data: sRGB { r: 0, g: 0, b: 172 } }
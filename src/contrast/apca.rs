@@ -0,0 +1,152 @@
+//! APCA-W3 (version 0.0.98G-4g) lightness contrast.
+//!
+//! Unlike `contrast_ratio` and the `delta_E_*` functions, APCA is polarity-aware:
+//! dark text on a light background gives a positive Lc, light text on a dark background a negative one,
+//! and the magnitudes are not the same when swapping text and background.
+
+use crate::rgb;
+
+const MAIN_TRC: f64 = 2.4;
+const RED_COEFFICIENT: f64 = 0.212_672_9;
+const GREEN_COEFFICIENT: f64 = 0.715_152_2;
+const BLUE_COEFFICIENT: f64 = 0.072_175;
+
+const NORMAL_BACKGROUND: f64 = 0.56;
+const NORMAL_TEXT: f64 = 0.57;
+const REVERSE_TEXT: f64 = 0.62;
+const REVERSE_BACKGROUND: f64 = 0.65;
+
+const BLACK_THRESHOLD: f64 = 0.022;
+const BLACK_CLAMP: f64 = 1.414;
+const SCALE: f64 = 1.14;
+const LOW_OFFSET: f64 = 0.027;
+const LOW_CLIP: f64 = 0.1;
+const DELTA_Y_MINIMUM: f64 = 0.0005;
+
+/// Commonly used minimum magnitudes of Lc.
+pub const BODY_TEXT: f64 = 75.0;
+pub const CONTENT_TEXT: f64 = 60.0;
+pub const LARGE_TEXT: f64 = 45.0;
+pub const NON_TEXT: f64 = 30.0;
+
+/// APCA's estimated screen luminance.
+/// This deliberately uses a plain 2.4 power curve instead of the piecewise sRGB curve of `sRGB::to_lrgb`,
+/// and soft-clamps near black to account for flare.
+fn screen_luminance(color: rgb::sRGB) -> f64 {
+    let channel = |value: u8| (f64::from(value) / 255.0).powf(MAIN_TRC);
+    let luminance = channel(color.r).mul_add(
+        RED_COEFFICIENT,
+        channel(color.g).mul_add(GREEN_COEFFICIENT, channel(color.b) * BLUE_COEFFICIENT),
+    );
+    if luminance < BLACK_THRESHOLD {
+        luminance + (BLACK_THRESHOLD - luminance).powf(BLACK_CLAMP)
+    } else {
+        luminance
+    }
+}
+
+/// Lightness contrast Lc of `text` drawn on `background`, roughly from `-108.0` to `106.0`.
+///
+/// Positive for dark text on a light background, negative for light text on a dark background.
+/// Values with a magnitude below about `7.5` are clipped to `0.0`.
+pub fn lightness_contrast(text: rgb::sRGB, background: rgb::sRGB) -> f64 {
+    let text = screen_luminance(text);
+    let background = screen_luminance(background);
+
+    if (background - text).abs() < DELTA_Y_MINIMUM {
+        return 0.0;
+    }
+
+    let contrast = if background > text {
+        let sapc = (background.powf(NORMAL_BACKGROUND) - text.powf(NORMAL_TEXT)) * SCALE;
+        if sapc < LOW_CLIP {
+            0.0
+        } else {
+            sapc - LOW_OFFSET
+        }
+    } else {
+        let sapc = (background.powf(REVERSE_BACKGROUND) - text.powf(REVERSE_TEXT)) * SCALE;
+        if sapc > -LOW_CLIP {
+            0.0
+        } else {
+            sapc + LOW_OFFSET
+        }
+    };
+    contrast * 100.0
+}
+
+/// Whichever of black or white text is more readable on `background`.
+pub fn best_text_color(background: rgb::sRGB) -> rgb::sRGB {
+    best_text_color_from(
+        background,
+        [rgb::sRGB::new(0, 0, 0), rgb::sRGB::new(255, 255, 255)],
+    )
+    .map_or_else(rgb::sRGB::default, |(color, _)| color)
+}
+
+/// The candidate with the highest magnitude of Lc when used as text on `background`, along with that Lc.
+pub fn best_text_color_from(
+    background: rgb::sRGB,
+    candidates: impl IntoIterator<Item = rgb::sRGB>,
+) -> Option<(rgb::sRGB, f64)> {
+    candidates
+        .into_iter()
+        .map(|text| (text, lightness_contrast(text, background)))
+        .max_by(|first, second| first.1.abs().total_cmp(&second.1.abs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contrast::apca;
+    use crate::rgb;
+
+    const DIFFERENCE: f64 = 1e-9;
+
+    fn gray(value: u8) -> rgb::sRGB {
+        rgb::sRGB::new(value, value, value)
+    }
+
+    #[test]
+    fn reference_values() {
+        // From the APCA-W3 readme
+        let pairs = [
+            (gray(0x88), gray(0xff), 63.056_469_930_209_424),
+            (gray(0xff), gray(0x88), -68.541_464_366_449_62),
+            (gray(0x00), gray(0xaa), 58.146_262_578_561_334),
+            (gray(0xaa), gray(0x00), -56.241_133_368_397_42),
+            (
+                rgb::sRGB::new(0x11, 0x22, 0x33),
+                rgb::sRGB::new(0xdd, 0xee, 0xff),
+                91.668_308_114_816_31,
+            ),
+            (
+                rgb::sRGB::new(0xdd, 0xee, 0xff),
+                rgb::sRGB::new(0x11, 0x22, 0x33),
+                -93.067_700_494_842_75,
+            ),
+        ];
+        for (text, background, expected) in pairs {
+            let result = apca::lightness_contrast(text, background);
+            assert!(
+                (result - expected).abs() < DIFFERENCE,
+                "{text} on {background}: {result} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn low_contrast_is_clipped() {
+        assert!(apca::lightness_contrast(gray(0x80), gray(0x80)).abs() < DIFFERENCE);
+        assert!(apca::lightness_contrast(gray(0x80), gray(0x84)).abs() < DIFFERENCE);
+    }
+
+    #[test]
+    fn best_text_color() {
+        assert_eq!(apca::best_text_color(gray(0xff)), gray(0x00));
+        assert_eq!(apca::best_text_color(gray(0x00)), gray(0xff));
+        assert_eq!(
+            apca::best_text_color(rgb::sRGB::new(98, 0, 255)),
+            gray(0xff)
+        );
+    }
+}
@@ -0,0 +1,101 @@
+#![allow(dead_code)]
+
+pub mod apca;
+
+use crate::rgb;
+
+/// WCAG 2.x text size categories.
+/// Large text is at least 18pt, or at least 14pt and bold.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TextSize {
+    Normal,
+    Large,
+}
+
+/// WCAG 2.x conformance levels for contrast (success criteria 1.4.3 and 1.4.6).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Level {
+    AA,
+    AAA,
+}
+
+impl Level {
+    pub const fn minimum_ratio(self, size: TextSize) -> f64 {
+        match (self, size) {
+            (Self::AA, TextSize::Large) => 3.0,
+            (Self::AA, TextSize::Normal) | (Self::AAA, TextSize::Large) => 4.5,
+            (Self::AAA, TextSize::Normal) => 7.0,
+        }
+    }
+}
+
+/// WCAG 2.x contrast ratio, from `1.0` for identical colors to `21.0` for black vs. white.
+///
+/// Symmetric, so the order of `first` and `second` doesn't matter.
+pub fn contrast_ratio(first: rgb::lRGB, second: rgb::lRGB) -> f64 {
+    let first = first.relative_luminance();
+    let second = second.relative_luminance();
+    (first.max(second) + 0.05) / (first.min(second) + 0.05)
+}
+
+pub fn passes(first: rgb::lRGB, second: rgb::lRGB, level: Level, size: TextSize) -> bool {
+    contrast_ratio(first, second) >= level.minimum_ratio(size)
+}
+pub fn passes_aa(first: rgb::lRGB, second: rgb::lRGB, size: TextSize) -> bool {
+    passes(first, second, Level::AA, size)
+}
+pub fn passes_aaa(first: rgb::lRGB, second: rgb::lRGB, size: TextSize) -> bool {
+    passes(first, second, Level::AAA, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contrast;
+    use crate::rgb;
+
+    const DIFFERENCE: f64 = 1e-2;
+
+    #[test]
+    fn black_vs_white() {
+        let black = rgb::sRGB::new(0, 0, 0).to_lrgb();
+        let white = rgb::sRGB::new(255, 255, 255).to_lrgb();
+        assert!((contrast::contrast_ratio(black, white) - 21.0).abs() < DIFFERENCE);
+        assert!((contrast::contrast_ratio(white, black) - 21.0).abs() < DIFFERENCE);
+        assert!((contrast::contrast_ratio(white, white) - 1.0).abs() < DIFFERENCE);
+    }
+
+    #[test]
+    fn gray_thresholds() {
+        // #767676 is the well-known lightest gray passing AA on white, #777777 just fails it
+        let white = rgb::sRGB::new(255, 255, 255).to_lrgb();
+        let passing = rgb::sRGB::new(0x76, 0x76, 0x76).to_lrgb();
+        let failing = rgb::sRGB::new(0x77, 0x77, 0x77).to_lrgb();
+        assert!((contrast::contrast_ratio(passing, white) - 4.54).abs() < DIFFERENCE);
+        assert!(contrast::passes_aa(
+            passing,
+            white,
+            contrast::TextSize::Normal
+        ));
+        assert!(!contrast::passes_aa(
+            failing,
+            white,
+            contrast::TextSize::Normal
+        ));
+        assert!(contrast::passes_aa(
+            failing,
+            white,
+            contrast::TextSize::Large
+        ));
+        assert!(!contrast::passes_aaa(
+            passing,
+            white,
+            contrast::TextSize::Normal
+        ));
+        assert!(contrast::passes_aaa(
+            passing,
+            white,
+            contrast::TextSize::Large
+        ));
+    }
+}
@@ -0,0 +1,448 @@
+#![allow(dead_code)]
+
+use crate::rgb;
+
+type Matrix = [[f64; 3]; 3];
+
+/// Which cone type is missing or anomalous.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Deficiency {
+    /// L cones, red-green.
+    Protan,
+    /// M cones, red-green.
+    Deutan,
+    /// S cones, blue-yellow.
+    Tritan,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Method {
+    /// Brettel, Viénot and Mollon (1997), projecting onto two half-planes.
+    Brettel,
+    /// Viénot, Brettel and Mollon (1999), a single plane that is only accurate for protans and deutans.
+    /// Tritans are simulated with `Brettel` instead.
+    Vienot,
+    /// Machado, Oliveira and Fernandes (2009), which also models anomalous trichromacy.
+    Machado,
+}
+
+/// A complete description of how to simulate a color vision deficiency.
+///
+/// `severity` goes from `0.0`, normal vision, to `1.0`, dichromacy (protanopia, deuteranopia or tritanopia).
+/// Brettel and Viénot only simulate dichromacy, so their lower severities are blended with the original color.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Simulation {
+    pub deficiency: Deficiency,
+    pub method: Method,
+    pub severity: f64,
+}
+
+impl Simulation {
+    /// Full-severity simulation with Machado et al., which is also used for the anomalous trichromacies.
+    pub const fn dichromacy(deficiency: Deficiency) -> Self {
+        Self {
+            deficiency,
+            method: Method::Machado,
+            severity: 1.0,
+        }
+    }
+
+    pub fn simulate(self, color: rgb::lRGB) -> rgb::lRGB {
+        let severity = self.severity.clamp(0.0, 1.0);
+        let simulated = match (self.method, self.deficiency) {
+            (Method::Machado, deficiency) => {
+                return apply(&machado(deficiency, severity), color).clamp();
+            }
+            (Method::Vienot, Deficiency::Protan) => apply(&VIENOT_PROTAN, color),
+            (Method::Vienot, Deficiency::Deutan) => apply(&VIENOT_DEUTAN, color),
+            (Method::Brettel | Method::Vienot, deficiency) => brettel(deficiency, color),
+        };
+        rgb::lRGB {
+            r: (simulated.r - color.r).mul_add(severity, color.r),
+            g: (simulated.g - color.g).mul_add(severity, color.g),
+            b: (simulated.b - color.b).mul_add(severity, color.b),
+        }
+        .clamp()
+    }
+}
+
+impl rgb::lRGB {
+    /// Color as seen with the given color vision deficiency, clamped to the sRGB gamut.
+    /// Convert the result with `to_oklab` to use it with any of the `delta_E_*` functions.
+    pub fn simulate_cvd(self, simulation: Simulation) -> Self {
+        simulation.simulate(self)
+    }
+}
+
+impl rgb::sRGB {
+    pub fn simulate_cvd(self, simulation: Simulation) -> Self {
+        self.to_lrgb().simulate_cvd(simulation).to_srgb()
+    }
+}
+
+fn apply(matrix: &Matrix, color: rgb::lRGB) -> rgb::lRGB {
+    let row = |row: [f64; 3]| row[0].mul_add(color.r, row[1].mul_add(color.g, row[2] * color.b));
+    rgb::lRGB {
+        r: row(matrix[0]),
+        g: row(matrix[1]),
+        b: row(matrix[2]),
+    }
+}
+
+/// Brettel et al. uses a different projection on each side of the plane containing the neutral axis and the anchor color.
+fn brettel(deficiency: Deficiency, color: rgb::lRGB) -> rgb::lRGB {
+    let (first, second, normal) = match deficiency {
+        Deficiency::Protan => (&BRETTEL_PROTAN.0, &BRETTEL_PROTAN.1, BRETTEL_PROTAN.2),
+        Deficiency::Deutan => (&BRETTEL_DEUTAN.0, &BRETTEL_DEUTAN.1, BRETTEL_DEUTAN.2),
+        Deficiency::Tritan => (&BRETTEL_TRITAN.0, &BRETTEL_TRITAN.1, BRETTEL_TRITAN.2),
+    };
+    let side = normal[0].mul_add(color.r, normal[1].mul_add(color.g, normal[2] * color.b));
+    if side >= 0.0 {
+        apply(first, color)
+    } else {
+        apply(second, color)
+    }
+}
+
+/// Linearly interpolates between the two closest tabulated severities.
+#[allow(clippy::cast_possible_truncation)] // severity is clamped, so the index is at most 10
+#[allow(clippy::cast_sign_loss)]
+#[allow(clippy::cast_precision_loss)]
+fn machado(deficiency: Deficiency, severity: f64) -> Matrix {
+    let table = match deficiency {
+        Deficiency::Protan => &MACHADO_PROTAN,
+        Deficiency::Deutan => &MACHADO_DEUTAN,
+        Deficiency::Tritan => &MACHADO_TRITAN,
+    };
+    let position = severity * 10.0;
+    let lower = (position.floor() as usize).min(9);
+    let t = position - lower as f64;
+
+    let mut matrix = table[lower];
+    for (row, upper_row) in matrix.iter_mut().zip(table[lower + 1]) {
+        for (value, upper_value) in row.iter_mut().zip(upper_row) {
+            *value = (upper_value - *value).mul_add(t, *value);
+        }
+    }
+    matrix
+}
+
+// Matrices operate on linear sRGB, precomputed from the LMS projections of each paper.
+// The Brettel tuples are the projection for each half-plane and the normal of the plane that separates them.
+
+const VIENOT_PROTAN: Matrix = [
+    [0.112_38, 0.887_62, 0.0],
+    [0.112_38, 0.887_62, 0.0],
+    [0.004_01, -0.004_01, 1.0],
+];
+const VIENOT_DEUTAN: Matrix = [
+    [0.292_75, 0.707_25, 0.0],
+    [0.292_75, 0.707_25, 0.0],
+    [-0.022_34, 0.022_34, 1.0],
+];
+
+const BRETTEL_PROTAN: (Matrix, Matrix, [f64; 3]) = (
+    [
+        [0.149_80, 1.195_48, -0.345_28],
+        [0.107_64, 0.848_64, 0.043_72],
+        [0.003_84, -0.005_40, 1.001_56],
+    ],
+    [
+        [0.145_70, 1.161_72, -0.307_42],
+        [0.108_16, 0.852_91, 0.038_92],
+        [0.003_86, -0.005_24, 1.001_39],
+    ],
+    [0.000_48, 0.003_93, -0.004_41],
+);
+const BRETTEL_DEUTAN: (Matrix, Matrix, [f64; 3]) = (
+    [
+        [0.364_77, 0.863_81, -0.228_58],
+        [0.262_94, 0.642_45, 0.094_62],
+        [-0.020_06, 0.027_28, 0.992_78],
+    ],
+    [
+        [0.372_98, 0.881_66, -0.254_64],
+        [0.259_54, 0.635_06, 0.105_40],
+        [-0.019_80, 0.027_84, 0.991_96],
+    ],
+    [-0.002_81, -0.006_11, 0.008_92],
+);
+const BRETTEL_TRITAN: (Matrix, Matrix, [f64; 3]) = (
+    [
+        [1.012_77, 0.135_48, -0.148_26],
+        [-0.012_43, 0.868_12, 0.144_31],
+        [0.075_89, 0.805_00, 0.119_11],
+    ],
+    [
+        [0.936_78, 0.189_79, -0.126_57],
+        [0.061_54, 0.815_26, 0.123_20],
+        [-0.375_62, 1.127_67, 0.247_96],
+    ],
+    [0.039_01, -0.027_88, -0.011_13],
+);
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Severities `0.0, 0.1, ..., 1.0`.
+const MACHADO_PROTAN: [Matrix; 11] = [
+    IDENTITY,
+    [
+        [0.856_167, 0.182_038, -0.038_205],
+        [0.029_342, 0.955_115, 0.015_544],
+        [-0.002_880, -0.001_563, 1.004_443],
+    ],
+    [
+        [0.734_766, 0.334_872, -0.069_637],
+        [0.051_840, 0.919_198, 0.028_963],
+        [-0.004_928, -0.004_209, 1.009_137],
+    ],
+    [
+        [0.630_323, 0.465_641, -0.095_964],
+        [0.069_181, 0.890_046, 0.040_773],
+        [-0.006_308, -0.007_724, 1.014_032],
+    ],
+    [
+        [0.539_009, 0.579_343, -0.118_352],
+        [0.082_546, 0.866_121, 0.051_332],
+        [-0.007_136, -0.011_959, 1.019_095],
+    ],
+    [
+        [0.458_064, 0.679_578, -0.137_642],
+        [0.092_785, 0.846_313, 0.060_902],
+        [-0.007_494, -0.016_807, 1.024_301],
+    ],
+    [
+        [0.385_450, 0.769_005, -0.154_455],
+        [0.100_526, 0.829_802, 0.069_673],
+        [-0.007_442, -0.022_190, 1.029_632],
+    ],
+    [
+        [0.319_627, 0.849_633, -0.169_261],
+        [0.106_241, 0.815_969, 0.077_790],
+        [-0.007_025, -0.028_051, 1.035_076],
+    ],
+    [
+        [0.259_411, 0.923_008, -0.182_420],
+        [0.110_296, 0.804_340, 0.085_364],
+        [-0.006_276, -0.034_346, 1.040_622],
+    ],
+    [
+        [0.203_876, 0.990_338, -0.194_214],
+        [0.112_975, 0.794_542, 0.092_483],
+        [-0.005_222, -0.041_043, 1.046_265],
+    ],
+    [
+        [0.152_286, 1.052_583, -0.204_868],
+        [0.114_503, 0.786_281, 0.099_216],
+        [-0.003_882, -0.048_116, 1.051_998],
+    ],
+];
+const MACHADO_DEUTAN: [Matrix; 11] = [
+    IDENTITY,
+    [
+        [0.866_435, 0.177_704, -0.044_139],
+        [0.049_567, 0.939_063, 0.011_370],
+        [-0.003_453, 0.007_233, 0.996_220],
+    ],
+    [
+        [0.760_729, 0.319_078, -0.079_807],
+        [0.090_568, 0.889_315, 0.020_117],
+        [-0.006_027, 0.013_325, 0.992_702],
+    ],
+    [
+        [0.675_425, 0.433_850, -0.109_275],
+        [0.125_303, 0.847_755, 0.026_942],
+        [-0.007_950, 0.018_572, 0.989_378],
+    ],
+    [
+        [0.605_511, 0.528_560, -0.134_071],
+        [0.155_318, 0.812_366, 0.032_316],
+        [-0.009_376, 0.023_176, 0.986_200],
+    ],
+    [
+        [0.547_494, 0.607_765, -0.155_259],
+        [0.181_692, 0.781_742, 0.036_566],
+        [-0.010_410, 0.027_275, 0.983_136],
+    ],
+    [
+        [0.498_864, 0.674_741, -0.173_604],
+        [0.205_199, 0.754_872, 0.039_929],
+        [-0.011_131, 0.030_969, 0.980_162],
+    ],
+    [
+        [0.457_771, 0.731_899, -0.189_670],
+        [0.226_409, 0.731_012, 0.042_579],
+        [-0.011_595, 0.034_333, 0.977_261],
+    ],
+    [
+        [0.422_823, 0.781_057, -0.203_881],
+        [0.245_752, 0.709_602, 0.044_646],
+        [-0.011_843, 0.037_423, 0.974_421],
+    ],
+    [
+        [0.392_952, 0.823_610, -0.216_562],
+        [0.263_559, 0.690_210, 0.046_232],
+        [-0.011_910, 0.040_281, 0.971_630],
+    ],
+    [
+        [0.367_322, 0.860_646, -0.227_968],
+        [0.280_085, 0.672_501, 0.047_413],
+        [-0.011_820, 0.042_940, 0.968_881],
+    ],
+];
+const MACHADO_TRITAN: [Matrix; 11] = [
+    IDENTITY,
+    [
+        [0.926_670, 0.092_514, -0.019_184],
+        [0.021_191, 0.964_503, 0.014_306],
+        [0.008_437, 0.054_813, 0.936_750],
+    ],
+    [
+        [0.895_720, 0.133_330, -0.029_050],
+        [0.029_997, 0.945_400, 0.024_603],
+        [0.013_027, 0.104_707, 0.882_266],
+    ],
+    [
+        [0.905_871, 0.127_791, -0.033_662],
+        [0.026_856, 0.941_251, 0.031_893],
+        [0.013_410, 0.148_296, 0.838_294],
+    ],
+    [
+        [0.948_035, 0.089_490, -0.037_526],
+        [0.014_364, 0.946_792, 0.038_844],
+        [0.010_853, 0.193_991, 0.795_156],
+    ],
+    [
+        [1.017_277, 0.027_029, -0.044_306],
+        [-0.006_113, 0.958_479, 0.047_634],
+        [0.006_379, 0.248_708, 0.744_913],
+    ],
+    [
+        [1.104_996, -0.046_633, -0.058_363],
+        [-0.032_137, 0.971_635, 0.060_503],
+        [0.001_336, 0.317_922, 0.680_742],
+    ],
+    [
+        [1.193_214, -0.109_812, -0.083_402],
+        [-0.058_496, 0.979_410, 0.079_086],
+        [-0.002_346, 0.403_492, 0.598_854],
+    ],
+    [
+        [1.257_728, -0.139_648, -0.118_081],
+        [-0.078_003, 0.975_409, 0.102_594],
+        [-0.003_316, 0.501_214, 0.502_102],
+    ],
+    [
+        [1.278_864, -0.125_333, -0.153_531],
+        [-0.084_748, 0.957_674, 0.127_074],
+        [-0.000_989, 0.601_151, 0.399_838],
+    ],
+    [
+        [1.255_528, -0.076_749, -0.178_779],
+        [-0.078_411, 0.930_809, 0.147_602],
+        [0.004_733, 0.691_367, 0.303_900],
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::cvd;
+    use crate::rgb;
+
+    const DIFFERENCE: f64 = 1e-4;
+    const ALL: [cvd::Deficiency; 3] = [
+        cvd::Deficiency::Protan,
+        cvd::Deficiency::Deutan,
+        cvd::Deficiency::Tritan,
+    ];
+    const METHODS: [cvd::Method; 3] = [
+        cvd::Method::Brettel,
+        cvd::Method::Vienot,
+        cvd::Method::Machado,
+    ];
+
+    #[test]
+    fn tables_keep_white() {
+        for table in [
+            &cvd::MACHADO_PROTAN,
+            &cvd::MACHADO_DEUTAN,
+            &cvd::MACHADO_TRITAN,
+        ] {
+            for matrix in table {
+                for row in matrix {
+                    assert!((row.iter().sum::<f64>() - 1.0).abs() < DIFFERENCE);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn grays_stay_gray() {
+        let gray = rgb::sRGB::new(128, 128, 128).to_lrgb();
+        for deficiency in ALL {
+            for method in METHODS {
+                for severity in [0.0, 0.35, 1.0] {
+                    let simulated = gray.simulate_cvd(cvd::Simulation {
+                        deficiency,
+                        method,
+                        severity,
+                    });
+                    assert!((simulated.r - gray.r).abs() < 1e-2);
+                    assert!((simulated.g - gray.g).abs() < 1e-2);
+                    assert!((simulated.b - gray.b).abs() < 1e-2);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_severity_is_normal_vision() {
+        let color = rgb::sRGB::new(255, 91, 175);
+        for deficiency in ALL {
+            for method in METHODS {
+                let simulation = cvd::Simulation {
+                    deficiency,
+                    method,
+                    severity: 0.0,
+                };
+                assert_eq!(color.simulate_cvd(simulation), color);
+            }
+        }
+    }
+
+    #[test]
+    fn red_green_collapses() {
+        let red = rgb::sRGB::new(255, 0, 0).to_lrgb();
+        let green = rgb::sRGB::new(0, 162, 0).to_lrgb();
+        let normal = red.to_oklab().delta_E_ab(green.to_oklab());
+        for deficiency in [cvd::Deficiency::Protan, cvd::Deficiency::Deutan] {
+            for method in METHODS {
+                let simulation = cvd::Simulation {
+                    deficiency,
+                    method,
+                    severity: 1.0,
+                };
+                let simulated = red
+                    .simulate_cvd(simulation)
+                    .to_oklab()
+                    .delta_E_ab(green.simulate_cvd(simulation).to_oklab());
+                assert!(simulated < normal, "{deficiency:?} {method:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn severity_is_interpolated() {
+        let color = rgb::sRGB::new(0, 197, 255).to_lrgb();
+        let at = |severity| {
+            color.simulate_cvd(cvd::Simulation {
+                deficiency: cvd::Deficiency::Tritan,
+                method: cvd::Method::Machado,
+                severity,
+            })
+        };
+        let middle = at(0.45);
+        let (lower, upper) = (at(0.4), at(0.5));
+        assert!((middle.g - f64::midpoint(lower.g, upper.g)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,204 @@
+#![allow(dead_code)]
+
+use crate::mean;
+use crate::oklab::Oklab;
+use crate::oklab::kd_tree::KdTree;
+use crate::render::Canvas;
+use crate::rgb::sRGB;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::io;
+use std::path::Path;
+
+/// Dominant colors of an image, found with k-means in Oklab so that clusters are perceptually round.
+///
+/// Centers start out with k-means++, which picks each one with a probability proportional to its squared
+/// `delta_E_ab` to the closest center so far, so the same seed always gives the same palette.
+/// Pixels are counted per distinct color first, so large images with few colors stay fast.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct KMeans {
+    /// Number of clusters, fewer are returned if the image doesn't have that many distinct colors.
+    pub count: usize,
+    pub seed: u64,
+    pub max_iterations: usize,
+    /// Stops once no center moves by more than this `delta_E_ab`.
+    pub tolerance: f64,
+    /// Pixels that are more transparent than this are left out.
+    pub minimum_alpha: u8,
+}
+
+impl Default for KMeans {
+    fn default() -> Self {
+        Self {
+            count: 8,
+            seed: 0,
+            max_iterations: 100,
+            tolerance: 1e-6,
+            minimum_alpha: 128,
+        }
+    }
+}
+
+impl KMeans {
+    /// # Errors
+    /// Returns any error from reading the PNG.
+    pub fn extract_png(&self, path: impl AsRef<Path>) -> io::Result<Vec<(sRGB, f64)>> {
+        let (canvas, alpha) = Canvas::read_png(path)?;
+        Ok(self.extract(
+            canvas
+                .pixels()
+                .iter()
+                .zip(alpha)
+                .filter(|&(_, alpha)| alpha >= self.minimum_alpha)
+                .map(|(&color, _)| color),
+        ))
+    }
+
+    /// Cluster centers along with the share of `pixels` in each cluster, largest share first.
+    /// Centers that end up outside of sRGB are brought back with `Oklab::to_srgb_gamut_mapped`.
+    #[allow(clippy::cast_precision_loss)] // pixel counts are far below 2^52
+    pub fn extract(&self, pixels: impl Iterator<Item = sRGB>) -> Vec<(sRGB, f64)> {
+        let mut pixels = pixels.collect::<Vec<_>>();
+        pixels.par_sort_unstable();
+        let total = pixels.len() as f64;
+        let colors = pixels
+            .into_iter()
+            .dedup_with_count()
+            .map(|(count, color)| (color.to_oklab(), count as f64))
+            .collect::<Vec<_>>();
+
+        let mut centers = self.initial_centers(&colors);
+        for _ in 0..self.max_iterations {
+            let clusters = assign(&colors, &centers);
+            let moved = centers
+                .iter_mut()
+                .enumerate()
+                .map(|(index, center)| {
+                    // An empty cluster keeps its center
+                    let next = mean::weighted_mean(
+                        colors
+                            .iter()
+                            .zip(&clusters)
+                            .filter(|&(_, &cluster)| cluster == index)
+                            .map(|(&color, _)| color),
+                    )
+                    .unwrap_or(*center);
+                    let step = next.delta_E_ab(*center);
+                    *center = next;
+                    step
+                })
+                .fold(0.0, f64::max);
+            if moved <= self.tolerance {
+                break;
+            }
+        }
+        let clusters = assign(&colors, &centers);
+
+        centers
+            .iter()
+            .enumerate()
+            .map(|(index, center)| {
+                let weight = colors
+                    .iter()
+                    .zip(&clusters)
+                    .filter(|&(_, &cluster)| cluster == index)
+                    .map(|(&(_, weight), _)| weight)
+                    .sum::<f64>();
+                (center.to_srgb_gamut_mapped(), weight / total)
+            })
+            .filter(|&(_, share)| share > 0.0)
+            .sorted_by(|first, second| second.1.total_cmp(&first.1).then(first.0.cmp(&second.0)))
+            .collect()
+    }
+
+    /// k-means++, where each distinct color is weighted by its pixel count.
+    fn initial_centers(&self, colors: &[(Oklab, f64)]) -> Vec<Oklab> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut centers = Vec::<Oklab>::with_capacity(self.count);
+        let mut distances = vec![f64::INFINITY; colors.len()];
+        while centers.len() < self.count {
+            let weights = colors
+                .iter()
+                .zip(&distances)
+                .map(|(&(_, count), &distance)| {
+                    if distance.is_infinite() {
+                        count
+                    } else {
+                        count * distance * distance
+                    }
+                })
+                .collect::<Vec<_>>();
+            let total = weights.iter().sum::<f64>();
+            // Every remaining color is already a center
+            if total <= 0.0 {
+                break;
+            }
+            let mut target = rng.random::<f64>() * total;
+            let index = weights
+                .iter()
+                .position(|&weight| {
+                    target -= weight;
+                    target < 0.0
+                })
+                .unwrap_or(weights.len() - 1);
+
+            let center = colors[index].0;
+            for (distance, &(color, _)) in distances.iter_mut().zip(colors) {
+                *distance = distance.min(color.delta_E_ab(center));
+            }
+            centers.push(center);
+        }
+        centers
+    }
+}
+
+/// Index of the closest center to each color.
+fn assign(colors: &[(Oklab, f64)], centers: &[Oklab]) -> Vec<usize> {
+    let tree = KdTree::new(centers.iter().copied().zip(0..));
+    colors
+        .par_iter()
+        .map(|&(color, _)| tree.nearest(color).map_or(0, |(index, _)| index))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::extract;
+    use crate::rgb;
+
+    #[test]
+    fn finds_flat_colors_and_shares() {
+        let (red, blue, yellow) = (
+            rgb::sRGB::new(255, 0, 0),
+            rgb::sRGB::new(0, 0, 255),
+            rgb::sRGB::new(255, 255, 0),
+        );
+        let pixels = [[red; 8], [red; 8], [blue; 8], [yellow; 8]].concat();
+        let palette = extract::KMeans {
+            count: 3,
+            ..extract::KMeans::default()
+        }
+        .extract(pixels.iter().copied());
+        assert_eq!(palette, [(red, 0.5), (blue, 0.25), (yellow, 0.25)]);
+
+        // Asking for more clusters than there are colors
+        let palette = extract::KMeans::default().extract(pixels.into_iter());
+        assert_eq!(palette.len(), 3);
+    }
+
+    #[test]
+    fn seed_is_reproducible() {
+        let gradient = (0..=255).map(|value| rgb::sRGB::new(value, 255 - value, value / 2));
+        let k_means = extract::KMeans {
+            count: 4,
+            seed: 7,
+            ..extract::KMeans::default()
+        };
+        let palette = k_means.extract(gradient.clone());
+        assert_eq!(palette.len(), 4);
+        assert_eq!(palette, k_means.extract(gradient.rev()));
+        assert!((palette.iter().map(|&(_, share)| share).sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,84 @@
+#![allow(dead_code)]
+
+use crate::oklab::{HueInterpolation, Oklab, Oklch};
+use crate::rgb;
+
+/// Position along evenly spaced stops: which pair of stops, and how far between them.
+#[allow(clippy::cast_precision_loss)] // sample and stop counts are small
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn locate(sample: usize, count: usize, stops: usize) -> (usize, f64) {
+    if count < 2 || stops < 2 {
+        return (0, 0.0);
+    }
+    let position = sample as f64 / (count - 1) as f64 * (stops - 1) as f64;
+    let index = (position.floor() as usize).min(stops - 2);
+    (index, position - index as f64)
+}
+
+/// `count` evenly spaced colors along a gradient through `stops`, interpolated in Oklab.
+/// The first and last samples are the first and last stops, and out-of-gamut samples are gamut mapped.
+pub fn sample_oklab(stops: &[Oklab], count: usize) -> Vec<rgb::sRGB> {
+    if stops.is_empty() {
+        return Vec::new();
+    }
+    (0..count)
+        .map(|sample| {
+            let (index, t) = locate(sample, count, stops.len());
+            let color = stops
+                .get(index + 1)
+                .map_or(stops[index], |&next| stops[index].mix(next, t));
+            color.to_srgb_gamut_mapped()
+        })
+        .collect()
+}
+
+/// `count` evenly spaced colors along a gradient through `stops`, interpolated in Oklch with the given hue method.
+/// The first and last samples are the first and last stops, and out-of-gamut samples are gamut mapped.
+pub fn sample_oklch(stops: &[Oklch], method: HueInterpolation, count: usize) -> Vec<rgb::sRGB> {
+    if stops.is_empty() {
+        return Vec::new();
+    }
+    (0..count)
+        .map(|sample| {
+            let (index, t) = locate(sample, count, stops.len());
+            let color = stops
+                .get(index + 1)
+                .map_or(stops[index], |&next| stops[index].mix(next, t, method));
+            color.to_srgb_gamut_mapped()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gradient;
+    use crate::oklab::HueInterpolation;
+    use crate::rgb;
+
+    #[test]
+    fn endpoints_are_stops() {
+        let stops = [
+            rgb::sRGB::new(98, 0, 255),
+            rgb::sRGB::new(0, 162, 0),
+            rgb::sRGB::new(255, 177, 0),
+        ];
+        let oklab = gradient::sample_oklab(&stops.map(rgb::sRGB::to_oklab), 7);
+        let oklch =
+            gradient::sample_oklch(&stops.map(rgb::sRGB::to_oklch), HueInterpolation::Longer, 7);
+        for samples in [oklab, oklch] {
+            assert_eq!(samples.len(), 7);
+            assert_eq!(samples[0], stops[0]);
+            assert_eq!(samples[3], stops[1]);
+            assert_eq!(samples[6], stops[2]);
+        }
+    }
+
+    #[test]
+    fn grays_stay_gray() {
+        let stops = [rgb::sRGB::new(0, 0, 0), rgb::sRGB::new(255, 255, 255)];
+        for color in gradient::sample_oklab(&stops.map(rgb::sRGB::to_oklab), 5) {
+            assert!(color.max() - color.min() <= 1);
+        }
+    }
+}
@@ -1,89 +1,227 @@
+mod contrast;
+mod cvd;
+mod extract;
+mod gradient;
+mod mean;
 mod oklab;
+mod palette;
+mod progress;
+mod quantize;
+mod render;
 mod rgb;
-use crate::oklab::Oklch;
-use crate::rgb::sRGB;
-use itertools::Itertools;
-use parking_lot::Mutex;
-use rayon::prelude::*;
+mod stats;
+
+const CHECKPOINT: &str = "palette.checkpoint";
+
+/// The palette from before the search, each bright color followed by a darker twin.
+const HAND_PICKED: [rgb::sRGB; 16] = [
+    rgb::sRGB::new(255, 0, 0),
+    rgb::sRGB::new(128, 64, 64),
+    rgb::sRGB::new(0, 64, 255),
+    rgb::sRGB::new(64, 79, 128),
+    rgb::sRGB::new(0, 255, 0),
+    rgb::sRGB::new(64, 128, 64),
+    rgb::sRGB::new(255, 255, 0),
+    rgb::sRGB::new(128, 128, 64),
+    rgb::sRGB::new(255, 0, 191),
+    rgb::sRGB::new(128, 64, 112),
+    rgb::sRGB::new(255, 164, 0),
+    rgb::sRGB::new(128, 105, 64),
+    rgb::sRGB::new(0, 255, 255),
+    rgb::sRGB::new(64, 128, 128),
+    rgb::sRGB::new(168, 168, 168),
+    rgb::sRGB::new(64, 64, 64),
+];
+
+/// The value following `name` in the command line arguments, like the `1` of `--seed 1`.
+fn argument(name: &str) -> Option<String> {
+    std::env::args()
+        .skip_while(|argument| argument != name)
+        .nth(1)
+}
+
+/// The arguments after the subcommand that aren't flags like `--seed` or their values.
+/// Every flag takes a value.
+fn positional_arguments() -> Vec<String> {
+    let mut arguments = std::env::args().skip(2);
+    let mut positional = Vec::new();
+    while let Some(argument) = arguments.next() {
+        if argument.starts_with("--") {
+            arguments.next();
+        } else {
+            positional.push(argument);
+        }
+    }
+    positional
+}
+
+/// Pass `--seed <n>` to reproduce an earlier run, otherwise a random seed is picked and printed.
+/// Only for the commands that use randomness, so that the others don't print a seed they ignore.
+fn seed() -> u64 {
+    let seed = argument("--seed").map_or_else(rand::random, |seed| {
+        seed.parse().expect("Seed must be an unsigned integer")
+    });
+    println!("Seed: {seed}");
+    seed
+}
+
+/// `extract <png>` prints the dominant colors of an image with their share of its pixels,
+/// pass `--count <n>` for another number of colors.
+fn extract_colors() {
+    let path = positional_arguments()
+        .into_iter()
+        .next()
+        .expect("Missing the PNG to extract colors from");
+    let count = argument("--count").map_or(8, |count| {
+        count.parse().expect("Count must be an unsigned integer")
+    });
+    let palette = extract::KMeans {
+        count,
+        seed: seed(),
+        ..Default::default()
+    }
+    .extract_png(&path)
+    .expect("Failed to read the PNG");
+    for (color, share) in palette {
+        println!("{color}, {}, {:.1}%", color.to_hex(), share * 100.0);
+    }
+}
+
+/// `report [<color>...]` prints an analysis of the given hex or CSS named colors, or of `HAND_PICKED` without any,
+/// pass `--csv <path>` to also write the distance of every pair.
+fn report_colors() {
+    let colors = positional_arguments()
+        .into_iter()
+        .map(|color| {
+            color
+                .parse::<rgb::sRGB>()
+                .unwrap_or_else(|error| panic!("{error}"))
+        })
+        .collect::<Vec<_>>();
+    let colors = if colors.is_empty() {
+        HAND_PICKED.to_vec()
+    } else {
+        colors
+    };
+    let report = palette::report::Analysis::default().analyze(&colors);
+    print!("{report}");
+    if let Some(path) = argument("--csv") {
+        report.write_csv(path).expect("Failed to write the CSV");
+    }
+}
 
 fn main() {
     let start_time = std::time::SystemTime::now();
 
     /*
-    let mut saved_colors = vec![
-        sRGB::new(255, 0, 0),
-        sRGB::new(128, 64, 64),
-        sRGB::new(0, 64, 255),
-        sRGB::new(64, 79, 128),
-        sRGB::new(0, 255, 0),
-        sRGB::new(64, 128, 64),
-        sRGB::new(255, 255, 0),
-        sRGB::new(128, 128, 64),
-        sRGB::new(255, 0, 191),
-        sRGB::new(128, 64, 112),
-        sRGB::new(255, 164, 0),
-        sRGB::new(128, 105, 64),
-        sRGB::new(0, 255, 255),
-        sRGB::new(64, 128, 128),
-        sRGB::new(168, 168, 168),
-        sRGB::new(64, 64, 64),
-    ];*/
-
-    /*
-    for color in &saved_colors {
+    for color in &HAND_PICKED {
         let oklch_version = color.to_oklch();
         println!("{oklch_version}");
     }
     */
 
-    let mut saved_colors = vec![sRGB::new(0, 0, 0), sRGB::new(255, 255, 255)];
+    match std::env::args().nth(1).as_deref() {
+        Some("extract") => return extract_colors(),
+        Some("report") => return report_colors(),
+        _ => {}
+    }
+    let seed = seed();
 
-    for _ in 1..=8 {
-        let saved_delta = Mutex::new(f64::NEG_INFINITY);
-        let saved_color = Mutex::new(sRGB::default());
-        let starting_colors = saved_colors.iter().map(|color| color.to_oklab());
+    // The first Ctrl-C stops the search and keeps the best colors so far, the second one exits right away
+    let cancellation = progress::Cancellation::default();
+    let handler_cancellation = cancellation.clone();
+    ctrlc::set_handler(move || {
+        if handler_cancellation.is_cancelled() {
+            std::process::exit(130);
+        }
+        handler_cancellation.cancel();
+    })
+    .expect("Failed to set the Ctrl-C handler");
+    let report = |progress: &progress::Progress| eprint!("\r{progress}\x1b[K");
+    let tracker = progress::Tracker {
+        cancellation,
+        report: &report,
+        ..Default::default()
+    };
 
-        sRGB::all_colors().par_bridge().for_each(|test_srgb| {
-            if test_srgb.to_oklab().l < 0.5 {
-                return;
-            }
-            let all_combos = starting_colors
-                .clone()
-                .chain(std::iter::once(test_srgb.to_oklab()))
-                .permutations(2);
+    // Every finished round is saved to CHECKPOINT, pass `--resume <checkpoint>` to continue from one with its settings
+    let resume = argument("--resume");
+    let (generator, resumed) = resume.as_ref().map_or_else(
+        || (palette::Generator::default(), palette::Palette::default()),
+        |path| {
+            let checkpoint =
+                palette::checkpoint::Checkpoint::load(path).expect("Failed to load the checkpoint");
+            println!("Resuming from {path}");
+            (checkpoint.generator.clone(), checkpoint.palette())
+        },
+    );
 
-            // TODO: find a good averaging method
-            let delta = all_combos
-                .map(|vector| vector[0].delta_E_Hyab(vector[1]))
-                .fold(f64::INFINITY, |a: f64, b: f64| a.min(b));
+    let print_pick = |color: rgb::sRGB, delta: f64| {
+        let twin = oklab::companion::Target::DARK_TWIN.derive(color);
+        print!("{color}, {}, {delta:.5?}", twin.color);
+        if twin.has_noticeable_drift() {
+            print!(", hue drift {:.1}°", twin.hue_drift());
+        }
+        println!();
+    };
+    for (&color, &delta) in resumed.colors.iter().zip(&resumed.deltas) {
+        print_pick(color, delta);
+    }
 
-            let mut locked_saved_delta = saved_delta.lock();
-            let mut locked_saved_color = saved_color.lock();
+    let mut printed = resumed.colors.len();
+    // A checkpoint left over from an earlier run could have other settings, so only offer one from this run
+    let mut checkpoint = resume;
+    let picked = generator.resume_tracked(resumed, &rgb::sRGB::all_colors(), &tracker, |palette| {
+        eprint!("\r\x1b[K");
+        print_pick(palette.colors[printed], palette.deltas[printed]);
+        printed += 1;
+        palette::checkpoint::Checkpoint::new(&generator, palette)
+            .save(CHECKPOINT)
+            .expect("Failed to write the checkpoint");
+        checkpoint = Some(CHECKPOINT.to_owned());
+    });
+    if tracker.is_cancelled() {
+        eprint!("\r\x1b[K");
+        for (&color, &delta) in picked.colors.iter().zip(&picked.deltas).skip(printed) {
+            print_pick(color, delta);
+        }
+        println!("Cancelled, the last color is only the best of the candidates checked so far");
+        if let Some(path) = &checkpoint {
+            println!("Continue with `--resume {path}`");
+        }
+    }
 
-            if (delta > *locked_saved_delta) && (!saved_colors.contains(&test_srgb)) {
-                *locked_saved_delta = delta;
-                drop(locked_saved_delta);
-                *locked_saved_color = test_srgb;
-                drop(locked_saved_color);
-            }
-        });
+    for (vision, delta) in &picked.minimum_deltas {
+        println!("{vision}: {delta:.5?}");
+    }
 
-        let saved_color = saved_color.into_inner();
+    let mut saved_colors = generator.starting_colors.clone();
+    saved_colors.extend(&picked.colors);
 
+    if !tracker.is_cancelled() {
+        let refinement = palette::refine::Annealing {
+            seed,
+            ..Default::default()
+        }
+        .refine(&generator, &saved_colors[generator.starting_colors.len()..]);
         println!(
-            "{saved_color}, {}, {:.5?}",
-            Oklch {
-                l: 0.5,
-                c: 0.1,
-                h: saved_color.to_oklch().h,
-                d65_reference_l: false
-            }
-            .to_srgb_closest(),
-            saved_delta.into_inner()
+            "Refined: {:.5?} -> {:.5?}, last improvement at iteration {}",
+            refinement.initial_score, refinement.score, refinement.last_improvement
         );
-        saved_colors.push(saved_color);
+        for (refined_color, refined_delta) in refinement
+            .palette
+            .colors
+            .iter()
+            .zip(&refinement.palette.deltas)
+        {
+            println!("{refined_color}, {refined_delta:.5?}");
+        }
+        saved_colors.truncate(generator.starting_colors.len());
+        saved_colors.extend(refinement.palette.colors);
     }
 
+    render::write_swatch_sheet(&saved_colors, "palette.png").expect("Failed to write palette.png");
+
     println!(
         "Total time: {:.3?}",
         start_time.elapsed().expect("Time went backwards")
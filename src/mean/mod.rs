@@ -0,0 +1,319 @@
+#![allow(dead_code)]
+
+use crate::oklab::{Hue, Oklab, Oklch};
+use rayon::prelude::*;
+
+const MEDIAN_ITERATIONS: usize = 200;
+const MEDIAN_TOLERANCE: f64 = 1e-9;
+
+/// Running weighted sums, which can be combined in any order for parallel reductions.
+#[derive(Copy, Clone, Debug, Default)]
+struct Sums {
+    l: f64,
+    a: f64,
+    b: f64,
+    c: f64,
+    weight: f64,
+}
+
+impl Sums {
+    fn add(self, color: Oklab, weight: f64) -> Self {
+        let color = color.to_unreferenced_white();
+        Self {
+            l: color.l.mul_add(weight, self.l),
+            a: color.a.mul_add(weight, self.a),
+            b: color.b.mul_add(weight, self.b),
+            c: color.chroma().mul_add(weight, self.c),
+            weight: self.weight + weight,
+        }
+    }
+    fn combine(self, other: Self) -> Self {
+        Self {
+            l: self.l + other.l,
+            a: self.a + other.a,
+            b: self.b + other.b,
+            c: self.c + other.c,
+            weight: self.weight + other.weight,
+        }
+    }
+
+    fn to_mean(self) -> Option<Oklab> {
+        (self.weight > 0.0).then(|| Oklab {
+            l: self.l / self.weight,
+            a: self.a / self.weight,
+            b: self.b / self.weight,
+            d65_reference_l: false,
+        })
+    }
+    /// The summed `a` and `b` are the chroma-weighted sums of the hue's cosine and sine,
+    /// so the hue of the Oklab mean is already the chroma-weighted circular mean.
+    /// The chroma is averaged separately, as averaging opposite hues in Oklab would cancel it out.
+    fn to_hue_mean(self) -> Option<Oklch> {
+        (self.weight > 0.0).then(|| Oklch {
+            l: self.l / self.weight,
+            c: self.c / self.weight,
+            h: Hue::from_radians(self.b.atan2(self.a)),
+            d65_reference_l: false,
+        })
+    }
+}
+
+/// Mean in Oklab, `None` if there are no colors.
+/// All functions here return colors without a white reference, converting the inputs as needed.
+pub fn mean(colors: impl IntoIterator<Item = Oklab>) -> Option<Oklab> {
+    weighted_mean(colors.into_iter().map(|color| (color, 1.0)))
+}
+
+/// Weighted mean in Oklab, `None` if the weights don't add up to something positive.
+pub fn weighted_mean(colors: impl IntoIterator<Item = (Oklab, f64)>) -> Option<Oklab> {
+    colors
+        .into_iter()
+        .fold(Sums::default(), |sums, (color, weight)| {
+            sums.add(color, weight)
+        })
+        .to_mean()
+}
+
+/// Mean in Oklch: lightness and chroma are averaged as usual, and hue is the circular mean weighted by chroma,
+/// so grays with meaningless hues don't count toward it.
+pub fn hue_mean(colors: impl IntoIterator<Item = Oklab>) -> Option<Oklch> {
+    colors
+        .into_iter()
+        .fold(Sums::default(), |sums, color| sums.add(color, 1.0))
+        .to_hue_mean()
+}
+
+/// The point with the smallest total `delta_E_ab` to all colors, which unlike the mean isn't dragged around by outliers.
+/// Found with Weiszfeld's algorithm.
+pub fn geometric_median(colors: &[Oklab]) -> Option<Oklab> {
+    let mut median = mean(colors.iter().copied())?;
+    for _ in 0..MEDIAN_ITERATIONS {
+        let next = weiszfeld_sums(colors.iter().copied(), median).step(median);
+        let step = next.delta_E_ab(median);
+        median = next;
+        if step < MEDIAN_TOLERANCE {
+            break;
+        }
+    }
+    Some(median)
+}
+
+pub fn par_mean(colors: &[Oklab]) -> Option<Oklab> {
+    colors
+        .par_iter()
+        .fold(Sums::default, |sums, &color| sums.add(color, 1.0))
+        .reduce(Sums::default, Sums::combine)
+        .to_mean()
+}
+
+pub fn par_weighted_mean(colors: &[(Oklab, f64)]) -> Option<Oklab> {
+    colors
+        .par_iter()
+        .fold(Sums::default, |sums, &(color, weight)| {
+            sums.add(color, weight)
+        })
+        .reduce(Sums::default, Sums::combine)
+        .to_mean()
+}
+
+pub fn par_hue_mean(colors: &[Oklab]) -> Option<Oklch> {
+    colors
+        .par_iter()
+        .fold(Sums::default, |sums, &color| sums.add(color, 1.0))
+        .reduce(Sums::default, Sums::combine)
+        .to_hue_mean()
+}
+
+pub fn par_geometric_median(colors: &[Oklab]) -> Option<Oklab> {
+    let mut median = par_mean(colors)?;
+    for _ in 0..MEDIAN_ITERATIONS {
+        let next = colors
+            .par_chunks(4096)
+            .map(|chunk| weiszfeld_sums(chunk.iter().copied(), median))
+            .reduce(WeiszfeldSums::default, WeiszfeldSums::combine)
+            .step(median);
+        let step = next.delta_E_ab(median);
+        median = next;
+        if step < MEDIAN_TOLERANCE {
+            break;
+        }
+    }
+    Some(median)
+}
+
+/// Sums for one Weiszfeld step, along with the colors that sit right on the current median.
+#[derive(Copy, Clone, Debug, Default)]
+struct WeiszfeldSums {
+    /// Colors weighted by their inverse distance to the median.
+    sums: Sums,
+    /// Number of colors on the median, which would have an infinite weight.
+    coincident: f64,
+}
+
+impl WeiszfeldSums {
+    fn combine(self, other: Self) -> Self {
+        Self {
+            sums: self.sums.combine(other.sums),
+            coincident: self.coincident + other.coincident,
+        }
+    }
+
+    /// The next median, with the Vardi–Zhang correction for colors on the current one:
+    /// they pull it toward where it is, and keep it there once they outweigh the pull of all other colors.
+    fn step(self, median: Oklab) -> Oklab {
+        // Every color is on the median
+        let Some(next) = self.sums.to_mean() else {
+            return median;
+        };
+        if self.coincident <= 0.0 {
+            return next;
+        }
+        let weight = self.sums.weight;
+        let pull = [
+            weight.mul_add(-median.l, self.sums.l),
+            weight.mul_add(-median.a, self.sums.a),
+            weight.mul_add(-median.b, self.sums.b),
+        ]
+        .map(|value| value * value)
+        .iter()
+        .sum::<f64>()
+        .sqrt();
+        let kept = (self.coincident / pull).min(1.0);
+        Oklab {
+            l: next.l.mul_add(1.0 - kept, median.l * kept),
+            a: next.a.mul_add(1.0 - kept, median.a * kept),
+            b: next.b.mul_add(1.0 - kept, median.b * kept),
+            d65_reference_l: false,
+        }
+    }
+}
+
+/// One Weiszfeld step: colors weighted by their inverse distance to `median`.
+/// Colors sitting right on it are counted separately, as their weight would be infinite.
+fn weiszfeld_sums(colors: impl Iterator<Item = Oklab>, median: Oklab) -> WeiszfeldSums {
+    colors.fold(WeiszfeldSums::default(), |sums, color| {
+        let distance = color.to_unreferenced_white().delta_E_ab(median);
+        if distance < MEDIAN_TOLERANCE {
+            WeiszfeldSums {
+                coincident: sums.coincident + 1.0,
+                ..sums
+            }
+        } else {
+            WeiszfeldSums {
+                sums: sums.sums.add(color, distance.recip()),
+                ..sums
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mean;
+    use crate::oklab::Oklab;
+    use crate::rgb;
+
+    const DIFFERENCE: f64 = 1e-6;
+
+    #[test]
+    fn black_and_white() {
+        let result = mean::mean([Oklab::BLACK, Oklab::WHITE]).unwrap();
+        assert!((result.l - 0.5).abs() < DIFFERENCE);
+        assert!(mean::mean(std::iter::empty()).is_none());
+        assert!(mean::weighted_mean([(Oklab::BLACK, 0.0)]).is_none());
+
+        let weighted = mean::weighted_mean([(Oklab::BLACK, 3.0), (Oklab::WHITE, 1.0)]).unwrap();
+        assert!((weighted.l - 0.25).abs() < DIFFERENCE);
+    }
+
+    #[test]
+    fn hue_mean_keeps_chroma() {
+        let red = rgb::sRGB::new(255, 0, 0).to_oklab();
+        let cyan = rgb::sRGB::new(0, 255, 255).to_oklab();
+        let yellow = rgb::sRGB::new(255, 255, 0).to_oklab();
+        let gray = rgb::sRGB::new(128, 128, 128).to_oklab();
+
+        // Opposite hues cancel out in Oklab, but not in Oklch
+        let result = mean::hue_mean([red, cyan]).unwrap();
+        assert!(mean::mean([red, cyan]).unwrap().chroma() < result.c);
+        assert!((result.c - f64::midpoint(red.chroma(), cyan.chroma())).abs() < DIFFERENCE);
+
+        // Grays don't pull the hue around
+        let result = mean::hue_mean([yellow, gray]).unwrap();
+        assert!(result.h.difference(yellow.hue()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn median_ignores_outliers() {
+        let gray = rgb::sRGB::new(128, 128, 128).to_oklab();
+        let colors = [
+            gray,
+            Oklab {
+                l: gray.l + 0.01,
+                ..gray
+            },
+            Oklab {
+                l: gray.l - 0.01,
+                ..gray
+            },
+            Oklab { a: 0.01, ..gray },
+            Oklab { a: -0.01, ..gray },
+            rgb::sRGB::new(255, 0, 255).to_oklab(),
+        ];
+        let median = mean::geometric_median(&colors).unwrap();
+        let average = mean::mean(colors).unwrap();
+        assert!(median.delta_E_ab(gray) < 0.01);
+        assert!(average.delta_E_ab(gray) > median.delta_E_ab(gray));
+    }
+
+    #[test]
+    fn median_of_coincident_colors() {
+        let red = rgb::sRGB::new(255, 0, 0).to_oklab();
+        let close = |first: Oklab, second: Oklab| first.delta_E_ab(second) < DIFFERENCE;
+        assert!(close(mean::geometric_median(&[red]).unwrap(), red));
+        assert!(close(mean::par_geometric_median(&[red; 5]).unwrap(), red));
+        assert!(mean::geometric_median(&[]).is_none());
+
+        // A majority of repeated colors is the median, however far away the others are
+        let blue = rgb::sRGB::new(0, 0, 255).to_oklab();
+        let white = Oklab::WHITE;
+        let colors = [red, red, red, blue, white];
+        assert!(close(mean::geometric_median(&colors).unwrap(), red));
+        assert!(close(mean::par_geometric_median(&colors).unwrap(), red));
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let srgb_colors =
+            itertools::iproduct!((0..=255).step_by(15), (0..=255).step_by(15), 0..=255)
+                .map(|(r, g, b)| rgb::sRGB::new(r, g, b))
+                .step_by(13)
+                .collect::<Vec<_>>();
+        let colors = srgb_colors
+            .iter()
+            .map(|color| color.to_oklab())
+            .collect::<Vec<_>>();
+        let weighted = srgb_colors
+            .iter()
+            .map(|color| (color.to_oklab(), f64::from(color.b % 7)))
+            .collect::<Vec<_>>();
+
+        let close = |first: Oklab, second: Oklab| first.delta_E_ab(second) < DIFFERENCE;
+        assert!(close(
+            mean::mean(colors.iter().copied()).unwrap(),
+            mean::par_mean(&colors).unwrap()
+        ));
+        assert!(close(
+            mean::weighted_mean(weighted.iter().copied()).unwrap(),
+            mean::par_weighted_mean(&weighted).unwrap()
+        ));
+        assert!(close(
+            mean::hue_mean(colors.iter().copied()).unwrap().to_oklab(),
+            mean::par_hue_mean(&colors).unwrap().to_oklab()
+        ));
+        assert!(close(
+            mean::geometric_median(&colors).unwrap(),
+            mean::par_geometric_median(&colors).unwrap()
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+//! Tints, shades and muted variants of a color, which keep its hue in Oklch.
+//!
+//! Going straight for the closest sRGB color of an out-of-gamut target, as `render::dark_twin` used to,
+//! can shift the hue noticeably. Here the chroma is brought down to the edge of the gamut at the target lightness instead,
+//! so the only hue drift left is from rounding to 8 bits, which `Companion::hue_difference` measures.
+
+use super::gamut::max_chroma;
+use super::{Oklch, rgb};
+
+/// A hue difference past this, in `Oklab` units, is about as noticeable as `Oklab::to_srgb_gamut_mapped`'s JND.
+pub const NOTICEABLE_HUE_DIFFERENCE: f64 = 0.02;
+
+/// Lightness and chroma to derive a companion color at, keeping the hue of the base color.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Target {
+    pub l: f64,
+    /// Brought down to the most that sRGB has at `l` and the base color's hue.
+    pub c: f64,
+}
+
+/// A derived color, along with the target it was derived for.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Companion {
+    pub color: rgb::sRGB,
+    /// The hue of the base color, and the lightness and chroma after gamut mapping but before rounding.
+    pub target: Oklch,
+}
+
+impl Target {
+    /// The darker companion that `main.rs` prints next to each palette entry.
+    pub const DARK_TWIN: Self = Self { l: 0.5, c: 0.1 };
+
+    /// Mixed with white by `amount` from `0.0` to `1.0`: lighter and less colorful.
+    pub fn tint(base: rgb::sRGB, amount: f64) -> Self {
+        let base = base.to_oklch();
+        Self {
+            l: (1.0 - base.l).mul_add(amount, base.l),
+            c: base.c * (1.0 - amount),
+        }
+    }
+
+    /// Mixed with black by `amount` from `0.0` to `1.0`: darker and less colorful.
+    pub fn shade(base: rgb::sRGB, amount: f64) -> Self {
+        let base = base.to_oklch();
+        Self {
+            l: base.l * (1.0 - amount),
+            c: base.c * (1.0 - amount),
+        }
+    }
+
+    /// Mixed with the gray of the same lightness by `amount` from `0.0` to `1.0`.
+    pub fn muted(base: rgb::sRGB, amount: f64) -> Self {
+        let base = base.to_oklch();
+        Self {
+            l: base.l,
+            c: base.c * (1.0 - amount),
+        }
+    }
+
+    /// The companion of `base` at this target.
+    /// Grays don't have a meaningful hue to keep, so their companions are gray too.
+    /// Out of the sRGB colors around the exact target, the closest one by `delta_E_ab` is picked,
+    /// which plain rounding in gamma-encoded sRGB doesn't always get.
+    pub fn derive(self, base: rgb::sRGB) -> Companion {
+        let hue = base.to_oklch().h;
+        let l = self.l.clamp(0.0, 1.0);
+        let c = if base.to_oklch().c < Oklch::ACHROMATIC {
+            0.0
+        } else {
+            self.c.clamp(0.0, max_chroma(l, hue))
+        };
+        let target = Oklch {
+            l,
+            c,
+            h: hue,
+            d65_reference_l: false,
+        };
+        let exact = target.to_oklab();
+        let color = exact
+            .to_lrgb()
+            .srgb_neighbors()
+            .min_by(|first, second| {
+                exact
+                    .delta_E_ab(first.to_oklab())
+                    .total_cmp(&exact.delta_E_ab(second.to_oklab()))
+                    .then(first.cmp(second))
+            })
+            .unwrap_or_default();
+        Companion { color, target }
+    }
+}
+
+impl Companion {
+    /// Hue of `color` minus the hue of the target in degrees, `0.0` if either is gray.
+    /// Near gray, large drifts in degrees can still be invisible, see `hue_difference`.
+    pub fn hue_drift(&self) -> f64 {
+        let actual = self.color.to_oklch();
+        if actual.c < Oklch::ACHROMATIC || self.target.c < Oklch::ACHROMATIC {
+            return 0.0;
+        }
+        actual.h.difference(self.target.h)
+    }
+
+    /// ΔH between `color` and the target in `Oklab` units, which scales the drift in degrees by the chroma,
+    /// the same way as the ΔH of CIELAB.
+    pub fn hue_difference(&self) -> f64 {
+        let actual = self.color.to_oklch();
+        2.0 * (actual.c * self.target.c).sqrt() * (self.hue_drift().to_radians() / 2.0).sin().abs()
+    }
+
+    pub fn has_noticeable_drift(&self) -> bool {
+        self.hue_difference() > NOTICEABLE_HUE_DIFFERENCE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oklab::companion::{self, Target};
+    use crate::rgb;
+
+    #[test]
+    fn hue_is_kept() {
+        for base in rgb::sRGB::corners().filter(|&color| color.min() == 0 && color.max() == 255) {
+            for target in [
+                Target::DARK_TWIN,
+                Target::tint(base, 0.5),
+                Target::shade(base, 0.5),
+                Target::muted(base, 0.5),
+            ] {
+                let companion = target.derive(base);
+                assert!(companion.hue_drift().abs() < 1.0);
+                assert!(!companion.has_noticeable_drift());
+                assert!((companion.color.to_oklch().l - target.l).abs() < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn chroma_is_gamut_mapped() {
+        // Yellow doesn't have anywhere near 0.3 chroma at this lightness
+        let yellow = rgb::sRGB::new(255, 255, 0);
+        let companion = Target { l: 0.4, c: 0.3 }.derive(yellow);
+        assert!(companion.target.c < 0.1);
+        assert!((companion.target.h.difference(yellow.to_oklch().h)).abs() < 1e-9);
+        assert!(companion.hue_difference() < companion::NOTICEABLE_HUE_DIFFERENCE);
+
+        let gray = rgb::sRGB::new(128, 128, 128);
+        let companion = Target::DARK_TWIN.derive(gray);
+        assert_eq!(companion.color.min(), companion.color.max());
+        assert!(companion.hue_drift().abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,497 @@
+//! The shape of the sRGB gamut in Oklab, with unreferenced lightness.
+//!
+//! `BoundaryTable` is exact up to the bisection tolerance, as it only uses the conversion matrices.
+//! The rest are Björn Ottosson's approximations from the Okhsv and Okhsl color pickers, which work on a hue given as the unit vector `(a, b)`.
+//! See <https://bottosson.github.io/posts/gamutclipping/> and <https://bottosson.github.io/posts/colorpicker/>.
+
+// Same names as in the reference implementation
+#![allow(clippy::many_single_char_names)]
+
+use super::{Hue, Oklab, Oklch};
+use crate::rgb;
+use rayon::prelude::*;
+use std::io;
+use std::path::Path;
+
+/// The most saturated color of a hue that fits in sRGB, where the gamut boundary has a corner.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cusp {
+    pub l: f64,
+    pub c: f64,
+}
+
+impl Cusp {
+    /// Slopes of the gamut boundary as `(C / L, C / (1 - L))`.
+    fn to_st(self) -> (f64, f64) {
+        (self.c / self.l, self.c / (1.0 - self.l))
+    }
+}
+
+/// Largest `S = C / L` that fits in sRGB, one Halley step on top of a polynomial fit.
+fn max_saturation(a: f64, b: f64) -> f64 {
+    // Which of red, green or blue goes below zero first
+    let (k, w): ([f64; 5], [f64; 3]) = if (-1.881_703_28_f64).mul_add(a, -0.809_364_93 * b) > 1.0 {
+        (
+            [
+                1.190_862_77,
+                1.765_767_28,
+                0.596_626_41,
+                0.755_151_97,
+                0.567_712_45,
+            ],
+            [4.076_741_662_1, -3.307_711_591_3, 0.230_969_929_2],
+        )
+    } else if 1.814_441_04_f64.mul_add(a, -1.194_452_76 * b) > 1.0 {
+        (
+            [
+                0.739_565_15,
+                -0.459_544_04,
+                0.082_854_27,
+                0.125_410_70,
+                0.145_032_04,
+            ],
+            [-1.268_438_004_6, 2.609_757_401_1, -0.341_319_396_5],
+        )
+    } else {
+        (
+            [
+                1.357_336_52,
+                -0.009_157_99,
+                -1.151_302_10,
+                -0.505_596_06,
+                0.006_921_67,
+            ],
+            [-0.004_196_086_3, -0.703_418_614_7, 1.707_614_701_0],
+        )
+    };
+
+    let saturation = k[4].mul_add(
+        a * b,
+        k[3].mul_add(a * a, k[2].mul_add(b, k[1].mul_add(a, k[0]))),
+    );
+    let (k_l, k_m, k_s) = lms_slopes(a, b);
+
+    let l_ = saturation.mul_add(k_l, 1.0);
+    let m_ = saturation.mul_add(k_m, 1.0);
+    let s_ = saturation.mul_add(k_s, 1.0);
+
+    let f = w[2].mul_add(s_.powi(3), w[1].mul_add(m_.powi(3), w[0] * l_.powi(3)));
+    let f1 = w[2].mul_add(
+        3.0 * k_s * s_ * s_,
+        w[1].mul_add(3.0 * k_m * m_ * m_, w[0] * 3.0 * k_l * l_ * l_),
+    );
+    let f2 = w[2].mul_add(
+        6.0 * k_s * k_s * s_,
+        w[1].mul_add(6.0 * k_m * k_m * m_, w[0] * 6.0 * k_l * k_l * l_),
+    );
+
+    saturation - f * f1 / f1.mul_add(f1, -0.5 * f * f2)
+}
+
+/// How much each of the cube roots of LMS changes with chroma along the hue `(a, b)`.
+fn lms_slopes(a: f64, b: f64) -> (f64, f64, f64) {
+    (
+        0.396_337_777_4_f64.mul_add(a, 0.215_803_757_3 * b),
+        (-0.105_561_345_8_f64).mul_add(a, -0.063_854_172_8 * b),
+        (-0.089_484_177_5_f64).mul_add(a, -1.291_485_548 * b),
+    )
+}
+
+pub fn find_cusp(a: f64, b: f64) -> Cusp {
+    let saturation = max_saturation(a, b);
+    let at_max = Oklab {
+        l: 1.0,
+        a: saturation * a,
+        b: saturation * b,
+        d65_reference_l: false,
+    }
+    .to_lrgb();
+    let l = (1.0 / at_max.max()).cbrt();
+    Cusp {
+        l,
+        c: l * saturation,
+    }
+}
+
+/// How far along the line from `(l0, 0)` to `(l1, c1)` the gamut boundary is, from `0.0` to `1.0`.
+pub fn find_gamut_intersection(a: f64, b: f64, l1: f64, c1: f64, l0: f64, cusp: Cusp) -> f64 {
+    if (l1 - l0).mul_add(cusp.c, -(cusp.l - l0) * c1) <= 0.0 {
+        // Lower half, where the boundary is a straight line to black
+        return cusp.c * l0 / c1.mul_add(cusp.l, cusp.c * (l0 - l1));
+    }
+
+    // Upper half, a straight line to white as a first guess, then one Halley step for each channel
+    let t = cusp.c * (l0 - 1.0) / c1.mul_add(cusp.l - 1.0, cusp.c * (l0 - l1));
+    let (k_l, k_m, k_s) = lms_slopes(a, b);
+    let l_dt = c1.mul_add(k_l, l1 - l0);
+    let m_dt = c1.mul_add(k_m, l1 - l0);
+    let s_dt = c1.mul_add(k_s, l1 - l0);
+
+    let l = l0.mul_add(1.0 - t, t * l1);
+    let c = t * c1;
+    let l_ = c.mul_add(k_l, l);
+    let m_ = c.mul_add(k_m, l);
+    let s_ = c.mul_add(k_s, l);
+
+    let lms = [l_.powi(3), m_.powi(3), s_.powi(3)];
+    let lms_dt = [
+        3.0 * l_dt * l_ * l_,
+        3.0 * m_dt * m_ * m_,
+        3.0 * s_dt * s_ * s_,
+    ];
+    let lms_dt2 = [
+        6.0 * l_dt * l_dt * l_,
+        6.0 * m_dt * m_dt * m_,
+        6.0 * s_dt * s_dt * s_,
+    ];
+    let dot = |row: [f64; 3], lms: [f64; 3]| {
+        row[2].mul_add(lms[2], row[1].mul_add(lms[1], row[0] * lms[0]))
+    };
+
+    let step = [
+        [4.076_741_662_1, -3.307_711_591_3, 0.230_969_929_2],
+        [-1.268_438_004_6, 2.609_757_401_1, -0.341_319_396_5],
+        [-0.004_196_086_3, -0.703_418_614_7, 1.707_614_701_0],
+    ]
+    .into_iter()
+    .map(|row| {
+        let value = dot(row, lms) - 1.0;
+        let first = dot(row, lms_dt);
+        let second = dot(row, lms_dt2);
+        let u = first / first.mul_add(first, -0.5 * value * second);
+        if u >= 0.0 { -value * u } else { f64::MAX }
+    })
+    .fold(f64::MAX, f64::min);
+
+    t + step
+}
+
+const TOE_K1: f64 = 0.206;
+const TOE_K2: f64 = 0.03;
+const TOE_K3: f64 = (1.0 + TOE_K1) / (1.0 + TOE_K2);
+
+/// Oklab lightness to the lightness of Okhsl and Okhsv, which matches CIELAB lightness better near black.
+pub fn toe(l: f64) -> f64 {
+    let shifted = TOE_K3.mul_add(l, -TOE_K1);
+    0.5 * (shifted + shifted.mul_add(shifted, 4.0 * TOE_K2 * TOE_K3 * l).sqrt())
+}
+
+pub fn toe_inverse(l: f64) -> f64 {
+    l.mul_add(l, TOE_K1 * l) / (TOE_K3 * (l + TOE_K2))
+}
+
+/// Smooth approximation of where the sRGB gamut boundary is for a hue, as `(S, T)` slopes.
+fn st_mid(a: f64, b: f64) -> (f64, f64) {
+    let s = 0.115_169_93
+        + 1.0
+            / a.mul_add(
+                a.mul_add(
+                    a.mul_add(
+                        4.698_910_13_f64.mul_add(a, 5.387_708_19_f64.mul_add(b, -4.248_945_61)),
+                        (-10.023_010_43_f64).mul_add(b, -2.137_049_48),
+                    ),
+                    1.751_984_01_f64.mul_add(b, -2.195_573_47),
+                ),
+                4.159_012_40_f64.mul_add(b, 7.447_789_70),
+            );
+    let t = 0.112_396_42
+        + 1.0
+            / a.mul_add(
+                a.mul_add(
+                    a.mul_add(
+                        (-0.146_618_72_f64)
+                            .mul_add(a, (-0.453_995_68_f64).mul_add(b, 0.002_992_15)),
+                        0.612_239_90_f64.mul_add(b, -0.270_879_43),
+                    ),
+                    0.901_481_23_f64.mul_add(b, 0.403_706_12),
+                ),
+                (-0.681_243_79_f64).mul_add(b, 1.613_203_20),
+            );
+    (s, t)
+}
+
+/// Okhsv saturation of an in-gamut color with lightness `l`, chroma `c` and hue `(a, b)`.
+/// `c` and `l` must be positive.
+pub fn okhsv_saturation(l: f64, c: f64, a: f64, b: f64) -> f64 {
+    const S_0: f64 = 0.5;
+    let (s_max, t_max) = find_cusp(a, b).to_st();
+    let k = 1.0 - S_0 / s_max;
+
+    // Where the line from white through the color meets the black to cusp edge of the gamut
+    let t = t_max / l.mul_add(t_max, c);
+    let c_v = t * c;
+    (S_0 + t_max) * c_v / t_max.mul_add(S_0, t_max * k * c_v)
+}
+
+/// Okhsl saturation of an in-gamut color with lightness `l`, chroma `c` and hue `(a, b)`.
+/// `c` must be positive and `l` strictly between `0.0` and `1.0`.
+pub fn okhsl_saturation(l: f64, c: f64, a: f64, b: f64) -> f64 {
+    const MID: f64 = 0.8;
+    const MID_INVERSE: f64 = 1.25;
+
+    let cusp = find_cusp(a, b);
+    let c_max = find_gamut_intersection(a, b, l, 1.0, l, cusp);
+    let (s_max, t_max) = cusp.to_st();
+    let k = c_max / (l * s_max).min((1.0 - l) * t_max);
+
+    let (s_mid, t_mid) = st_mid(a, b);
+    let (c_a, c_b) = (l * s_mid, (1.0 - l) * t_mid);
+    let c_mid = 0.9
+        * k
+        * (1.0 / (1.0 / c_a.powi(4) + 1.0 / c_b.powi(4)))
+            .sqrt()
+            .sqrt();
+
+    let (c_a, c_b) = (l * 0.4, (1.0 - l) * 0.8);
+    let c_0 = (1.0 / (1.0 / (c_a * c_a) + 1.0 / (c_b * c_b))).sqrt();
+
+    if c < c_mid {
+        let k_1 = MID * c_0;
+        let k_2 = 1.0 - k_1 / c_mid;
+        c / k_2.mul_add(c, k_1) * MID
+    } else {
+        let k_0 = c_mid;
+        let k_1 = (1.0 - MID) * c_mid * c_mid * MID_INVERSE * MID_INVERSE / c_0;
+        let k_2 = 1.0 - k_1 / (c_max - c_mid);
+        let t = (c - k_0) / k_2.mul_add(c - k_0, k_1);
+        (1.0 - MID).mul_add(t, MID)
+    }
+}
+
+const BISECTION_STEPS: usize = 48;
+/// Chroma steps for finding where the boundary roughly is before bisecting.
+const SCAN_STEPS: usize = 256;
+/// sRGB never goes past a chroma of 0.33.
+const SCAN_CHROMA: f64 = 0.4;
+
+/// Largest chroma at lightness `l` and `hue` that still fits in sRGB, `0.0` outside of `0.0..1.0`.
+///
+/// Near blue the gamut is slightly concave in Oklab, so going out from the gray axis can leave the gamut and enter it again.
+/// Because of that, the last fitting chroma of a coarse scan is found first, and the bisection only starts from there.
+#[allow(clippy::cast_precision_loss)] // SCAN_STEPS is small
+pub fn max_chroma(l: f64, hue: Hue) -> f64 {
+    if l <= 0.0 || l >= 1.0 {
+        return 0.0;
+    }
+    let fits = |c| {
+        Oklch {
+            l,
+            c,
+            h: hue,
+            d65_reference_l: false,
+        }
+        .to_oklab()
+        .in_gamut()
+    };
+    let step = SCAN_CHROMA / SCAN_STEPS as f64;
+    let Some(last) = (0..SCAN_STEPS)
+        .rev()
+        .find(|&index| fits(index as f64 * step))
+    else {
+        return 0.0;
+    };
+
+    let (mut min, mut max) = (last as f64 * step, (last + 1) as f64 * step);
+    for _ in 0..BISECTION_STEPS {
+        let middle = f64::midpoint(min, max);
+        if fits(middle) {
+            min = middle;
+        } else {
+            max = middle;
+        }
+    }
+    min
+}
+
+/// The cusp of `hue`, without the approximations of `find_cusp`.
+///
+/// The most saturated color of every hue has one channel at `0.0` and another at `1.0`,
+/// so the cusp is on those edges of the cube, which go around all hues.
+/// Hues don't always increase along them, from cyan to blue they go past blue's hue and come back,
+/// so every point of the edges with the right hue is found with a scan and bisection, and the most chromatic one wins.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)] // edges are 0 to 5, and SCAN_STEPS is small
+pub fn exact_cusp(hue: Hue) -> Cusp {
+    // Goes from red at `0.0` through yellow, green, cyan, blue and magenta, and back to red at `6.0`
+    let ring = |t: f64| {
+        let (edge, t) = (t.rem_euclid(6.0).floor(), t.rem_euclid(1.0));
+        let (r, g, b) = match edge as u8 {
+            0 => (1.0, t, 0.0),
+            1 => (1.0 - t, 1.0, 0.0),
+            2 => (0.0, 1.0, t),
+            3 => (0.0, 1.0 - t, 1.0),
+            4 => (t, 0.0, 1.0),
+            _ => (1.0, 0.0, 1.0 - t),
+        };
+        rgb::lRGB { r, g, b }.to_oklab()
+    };
+    let difference = |t| ring(t).hue().difference(hue);
+
+    let step = 6.0 / (6 * SCAN_STEPS) as f64;
+    (0..6 * SCAN_STEPS)
+        .map(|index| (index as f64 * step, (index + 1) as f64 * step))
+        .filter(|&(min, max)| {
+            let (before, after) = (difference(min), difference(max));
+            // Far from `hue`, the difference jumps from 180 to -180 instead
+            before <= 0.0 && after >= 0.0 && after - before < 90.0
+        })
+        .map(|(mut min, mut max)| {
+            for _ in 0..BISECTION_STEPS {
+                let middle = f64::midpoint(min, max);
+                if difference(middle) < 0.0 {
+                    min = middle;
+                } else {
+                    max = middle;
+                }
+            }
+            let cusp = ring(f64::midpoint(min, max));
+            Cusp {
+                l: cusp.l,
+                c: cusp.chroma(),
+            }
+        })
+        .max_by(|first, second| first.c.total_cmp(&second.c))
+        .unwrap_or(Cusp { l: 0.0, c: 0.0 })
+}
+
+/// One hue of a `BoundaryTable`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundaryRow {
+    pub hue: Hue,
+    pub cusp: Cusp,
+    /// Largest chroma at each of `BoundaryTable::lightnesses`.
+    pub max_chroma: Vec<f64>,
+}
+
+/// The edge of the sRGB gamut in Oklch: the cusp and the largest chroma at evenly spaced lightnesses, for evenly spaced hues.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundaryTable {
+    /// From `0.0` to `1.0`, both included.
+    pub lightnesses: Vec<f64>,
+    pub rows: Vec<BoundaryRow>,
+}
+
+impl BoundaryTable {
+    /// `hue_steps` hues starting at 0°, and `lightness_steps + 1` lightnesses.
+    #[allow(clippy::cast_precision_loss)] // table sizes are small
+    pub fn new(hue_steps: usize, lightness_steps: usize) -> Self {
+        let lightnesses = (0..=lightness_steps)
+            .map(|step| step as f64 / lightness_steps.max(1) as f64)
+            .collect::<Vec<_>>();
+        let rows = (0..hue_steps)
+            .into_par_iter()
+            .map(|step| {
+                let hue = Hue::from_turns(step as f64 / hue_steps as f64);
+                BoundaryRow {
+                    hue,
+                    cusp: exact_cusp(hue),
+                    max_chroma: lightnesses.iter().map(|&l| max_chroma(l, hue)).collect(),
+                }
+            })
+            .collect();
+        Self { lightnesses, rows }
+    }
+
+    /// Volume of the gamut in Oklab, integrating the area of each hue slice with the trapezoidal rule.
+    /// Slices are wedges, so their contribution is the integral of `c² / 2` over lightness, times the hue step in radians.
+    #[allow(clippy::cast_precision_loss)] // table sizes are small
+    pub fn volume(&self) -> f64 {
+        if self.rows.is_empty() {
+            return 0.0;
+        }
+        let hue_step = std::f64::consts::TAU / self.rows.len() as f64;
+        self.rows
+            .iter()
+            .map(|row| {
+                self.lightnesses
+                    .windows(2)
+                    .zip(row.max_chroma.windows(2))
+                    .map(|(l, c)| (l[1] - l[0]) * f64::midpoint(c[0] * c[0], c[1] * c[1]) / 2.0)
+                    .sum::<f64>()
+                    * hue_step
+            })
+            .sum()
+    }
+
+    /// # Errors
+    /// Returns any error from writing the file.
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+/// CSV with one line per hue: the hue in degrees, the cusp, and the largest chroma at each lightness,
+/// with a header naming the lightness of each column.
+impl std::fmt::Display for BoundaryTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "hue,cusp_l,cusp_c")?;
+        for l in &self.lightnesses {
+            write!(f, ",{l}")?;
+        }
+        writeln!(f)?;
+        for row in &self.rows {
+            write!(f, "{},{},{}", row.hue.degrees(), row.cusp.l, row.cusp.c)?;
+            for c in &row.max_chroma {
+                write!(f, ",{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oklab::{self, gamut};
+    use crate::rgb;
+
+    #[test]
+    fn cusps_are_corners() {
+        // The cusp of every primary and secondary hue is the color itself
+        for corner in rgb::sRGB::corners().filter(|&color| color.min() == 0 && color.max() == 255) {
+            let corner = corner.to_oklch();
+            let exact = gamut::exact_cusp(corner.h);
+            assert!((exact.l - corner.l).abs() < 1e-4);
+            assert!((exact.c - corner.c).abs() < 1e-6);
+
+            let approximate = corner.to_oklab();
+            let approximate = gamut::find_cusp(approximate.a / corner.c, approximate.b / corner.c);
+            assert!((approximate.l - corner.l).abs() < 1e-3);
+            assert!((approximate.c - corner.c).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn volume_matches_sampling() {
+        let table = gamut::BoundaryTable::new(72, 50);
+        // Count the points of a grid over Oklab that are in sRGB
+        let step = 0.02;
+        let inside = itertools::iproduct!(0..=50, -20..=20, -20..=20)
+            .filter(|&(l, a, b)| {
+                oklab::Oklab {
+                    l: f64::from(l) * step,
+                    a: f64::from(a) * step,
+                    b: f64::from(b) * step,
+                    d65_reference_l: false,
+                }
+                .in_gamut()
+            })
+            .count();
+        #[allow(clippy::cast_precision_loss)] // small count
+        let sampled = inside as f64 * step.powi(3);
+        assert!((table.volume() / sampled - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn csv_has_a_column_per_lightness() {
+        let csv = gamut::BoundaryTable::new(4, 10).to_string();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("hue,cusp_l,cusp_c,0,0.1,"));
+        assert!(lines.iter().all(|line| line.split(',').count() == 14));
+        assert!(lines[1].starts_with("0,"));
+    }
+}
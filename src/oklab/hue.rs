@@ -0,0 +1,81 @@
+/// Hue angle, always normalized to `[0, 360)` degrees.
+///
+/// `0` is on the positive `Oklab::a` axis, representing redness, and angles go counterclockwise towards `Oklab::b`, yellowness.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct Hue(f64);
+
+impl Hue {
+    pub fn from_degrees(degrees: f64) -> Self {
+        let normalized = degrees.rem_euclid(360.0);
+        // Tiny negative angles round up to exactly 360
+        Self(if normalized >= 360.0 { 0.0 } else { normalized })
+    }
+    pub fn from_radians(radians: f64) -> Self {
+        Self::from_degrees(radians.to_degrees())
+    }
+    pub fn from_turns(turns: f64) -> Self {
+        Self::from_degrees(turns * 360.0)
+    }
+
+    pub const fn degrees(self) -> f64 {
+        self.0
+    }
+    pub const fn radians(self) -> f64 {
+        self.0.to_radians()
+    }
+    pub fn turns(self) -> f64 {
+        self.0 / 360.0
+    }
+
+    /// Shortest signed difference from `other` to `self`, in degrees from `-180` (exclusive) to `180` (inclusive).
+    /// Positive if `self` is counterclockwise of `other`.
+    pub fn difference(self, other: Self) -> f64 {
+        let difference = (self.0 - other.0).rem_euclid(360.0);
+        if difference > 180.0 {
+            difference - 360.0
+        } else {
+            difference
+        }
+    }
+}
+
+impl std::fmt::Display for Hue {
+    /// Display in degrees: `284.07°`, respecting the precision if there is one.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)?;
+        write!(f, "°")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oklab::Hue;
+
+    const DIFFERENCE: f64 = 1e-9;
+
+    #[test]
+    fn normalization() {
+        assert!((Hue::from_degrees(-90.0).degrees() - 270.0).abs() < DIFFERENCE);
+        assert!((Hue::from_degrees(720.0).degrees()).abs() < DIFFERENCE);
+        assert!(Hue::from_degrees(-1e-20).degrees() < 360.0);
+        assert!(
+            (Hue::from_radians(-std::f64::consts::FRAC_PI_2).degrees() - 270.0).abs() < DIFFERENCE
+        );
+        assert!((Hue::from_turns(1.25).degrees() - 90.0).abs() < DIFFERENCE);
+        assert!((Hue::from_degrees(90.0).turns() - 0.25).abs() < DIFFERENCE);
+    }
+
+    #[test]
+    fn shortest_difference() {
+        let first = Hue::from_degrees(10.0);
+        let second = Hue::from_degrees(350.0);
+        assert!((first.difference(second) - 20.0).abs() < DIFFERENCE);
+        assert!((second.difference(first) + 20.0).abs() < DIFFERENCE);
+        assert!((Hue::from_degrees(180.0).difference(Hue::default()) - 180.0).abs() < DIFFERENCE);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(format!("{:.1}", Hue::from_degrees(-75.94)), "284.1°");
+    }
+}
@@ -0,0 +1,228 @@
+//! A k-d tree for "nearest color in a set" queries under `Oklab::delta_E_ab`,
+//! so snapping many colors to a palette or to the sRGB cube doesn't need a linear scan each time.
+
+use super::Oklab;
+use crate::rgb;
+use rayon::prelude::*;
+
+/// Slices longer than this are split in parallel while building.
+const PARALLEL_LENGTH: usize = 1 << 14;
+
+/// Colors along with an item each, such as the `sRGB` they came from or their index in a palette.
+///
+/// The tree is stored implicitly: the median of each slice along its axis is in the middle,
+/// with the smaller colors before it and the larger ones after it, and the axes going through `l`, `a` and `b`.
+/// Results are sorted by `delta_E_ab`, then by item, so ties don't depend on the order the colors came in.
+#[derive(Clone, Debug)]
+pub struct KdTree<T> {
+    points: Vec<(Oklab, T)>,
+}
+
+impl<T: Copy + Ord + Send> KdTree<T> {
+    pub fn new(points: impl IntoIterator<Item = (Oklab, T)>) -> Self {
+        let mut points = points.into_iter().collect::<Vec<_>>();
+        build(&mut points, 0);
+        Self { points }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The closest item to `target` along with its `delta_E_ab`, `None` if the tree is empty.
+    pub fn nearest(&self, target: Oklab) -> Option<(T, f64)> {
+        self.k_nearest(target, 1).first().copied()
+    }
+
+    /// The `k` closest items to `target`, closest first.
+    pub fn k_nearest(&self, target: Oklab, k: usize) -> Vec<(T, f64)> {
+        if k == 0 {
+            return Vec::new();
+        }
+        self.search(target, k, f64::INFINITY)
+    }
+
+    /// Every item at most `radius` away from `target`, closest first.
+    pub fn within(&self, target: Oklab, radius: f64) -> Vec<(T, f64)> {
+        self.search(target, usize::MAX, radius)
+    }
+
+    fn search(&self, target: Oklab, k: usize, radius: f64) -> Vec<(T, f64)> {
+        let mut search = Search {
+            target,
+            k,
+            radius,
+            found: Vec::new(),
+        };
+        search.visit(&self.points, 0);
+        let mut found = search.found;
+        // `within` collects without sorting
+        found.sort_unstable_by(|first, second| {
+            first.1.total_cmp(&second.1).then(first.0.cmp(&second.0))
+        });
+        found
+    }
+}
+
+impl KdTree<rgb::sRGB> {
+    /// Indexes `colors` by their own Oklab values.
+    pub fn from_colors(colors: impl Iterator<Item = rgb::sRGB> + Send) -> Self {
+        Self::new(
+            colors
+                .par_bridge()
+                .map(|color| (color.to_oklab(), color))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Every sRGB color, for snapping many colors to the cube. Takes a few seconds to build and about 670 MB,
+    /// at 40 bytes for each of the 2^24 colors, so keep it only as long as it's needed.
+    pub fn cube() -> Self {
+        Self::from_colors(rgb::sRGB::all_colors())
+    }
+}
+
+const fn coordinate(color: Oklab, depth: usize) -> f64 {
+    match depth % 3 {
+        0 => color.l,
+        1 => color.a,
+        _ => color.b,
+    }
+}
+
+fn build<T: Send>(points: &mut [(Oklab, T)], depth: usize) {
+    let length = points.len();
+    if length <= 1 {
+        return;
+    }
+    let middle = length / 2;
+    points.select_nth_unstable_by(middle, |first, second| {
+        coordinate(first.0, depth).total_cmp(&coordinate(second.0, depth))
+    });
+    let (before, after) = points.split_at_mut(middle);
+    let after = &mut after[1..];
+    if length > PARALLEL_LENGTH {
+        rayon::join(|| build(before, depth + 1), || build(after, depth + 1));
+    } else {
+        build(before, depth + 1);
+        build(after, depth + 1);
+    }
+}
+
+/// State of a single query: `k` closest items within `radius`, where `usize::MAX` means any number of them.
+struct Search<T> {
+    target: Oklab,
+    k: usize,
+    radius: f64,
+    /// Sorted unless `k` is `usize::MAX`.
+    found: Vec<(T, f64)>,
+}
+
+impl<T: Copy + Ord> Search<T> {
+    /// Largest delta that can still make it into `found`.
+    fn bound(&self) -> f64 {
+        match self.found.last() {
+            Some(&(_, delta)) if self.found.len() >= self.k => delta,
+            _ => self.radius,
+        }
+    }
+
+    fn offer(&mut self, item: T, delta: f64) {
+        if delta > self.radius {
+            return;
+        }
+        if self.k == usize::MAX {
+            self.found.push((item, delta));
+            return;
+        }
+        let index = self.found.partition_point(|&(other, other_delta)| {
+            other_delta.total_cmp(&delta).then(other.cmp(&item)).is_lt()
+        });
+        if index < self.k {
+            self.found.insert(index, (item, delta));
+            self.found.truncate(self.k);
+        }
+    }
+
+    fn visit(&mut self, points: &[(Oklab, T)], depth: usize) {
+        if points.is_empty() {
+            return;
+        }
+        let middle = points.len() / 2;
+        let (color, item) = points[middle];
+        self.offer(item, self.target.delta_E_ab(color));
+
+        let (before, after) = (&points[..middle], &points[middle + 1..]);
+        let difference = coordinate(self.target, depth) - coordinate(color, depth);
+        let (near, far) = if difference < 0.0 {
+            (before, after)
+        } else {
+            (after, before)
+        };
+        self.visit(near, depth + 1);
+        // Ties are kept, as the far side could have an item that sorts first
+        if difference.abs() <= self.bound() {
+            self.visit(far, depth + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oklab::{self, kd_tree::KdTree};
+    use crate::rgb;
+    use itertools::Itertools;
+
+    /// Every 17th value per channel, small enough to scan in debug builds.
+    fn coarse_cube() -> impl Iterator<Item = rgb::sRGB> + Clone + Send {
+        itertools::iproduct!(0..=15, 0..=15, 0..=15)
+            .map(|(r, g, b): (u8, u8, u8)| rgb::sRGB::new(r * 17, g * 17, b * 17))
+    }
+
+    fn targets() -> impl Iterator<Item = oklab::Oklab> {
+        itertools::iproduct!(0..=5, -2..=2, -2..=2).map(|(l, a, b)| oklab::Oklab {
+            l: f64::from(l) / 5.0,
+            a: f64::from(a) / 5.0,
+            b: f64::from(b) / 5.0,
+            d65_reference_l: false,
+        })
+    }
+
+    fn linear_scan(target: oklab::Oklab) -> Vec<(rgb::sRGB, f64)> {
+        coarse_cube()
+            .map(|color| (color, target.delta_E_ab(color.to_oklab())))
+            .sorted_by(|first, second| first.1.total_cmp(&second.1).then(first.0.cmp(&second.0)))
+            .collect()
+    }
+
+    #[test]
+    fn queries_match_linear_scan() {
+        let tree = KdTree::from_colors(coarse_cube());
+        assert_eq!(tree.len(), 16 * 16 * 16);
+        for target in targets() {
+            let expected = linear_scan(target);
+            assert_eq!(tree.nearest(target), expected.first().copied());
+            assert_eq!(tree.k_nearest(target, 5), expected[..5]);
+            let within = expected
+                .iter()
+                .copied()
+                .take_while(|&(_, delta)| delta <= 0.1)
+                .collect_vec();
+            assert_eq!(tree.within(target, 0.1), within);
+        }
+    }
+
+    #[test]
+    fn ties_prefer_the_smaller_item() {
+        let gray = rgb::sRGB::new(128, 128, 128).to_oklab();
+        let tree = KdTree::new([(gray, 3), (gray, 1), (gray, 2)]);
+        assert_eq!(tree.nearest(gray), Some((1, 0.0)));
+        assert_eq!(tree.k_nearest(gray, 2), [(1, 0.0), (2, 0.0)]);
+        assert!(tree.k_nearest(gray, 0).is_empty());
+        assert!(KdTree::<usize>::new([]).nearest(gray).is_none());
+    }
+}
@@ -1,7 +1,13 @@
 #![allow(dead_code)]
 
+pub mod companion;
+pub mod gamut;
+mod hue;
+pub mod kd_tree;
+
+pub use hue::Hue;
+
 use crate::rgb;
-use parking_lot::Mutex;
 use rayon::prelude::*;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -16,10 +22,81 @@ pub struct Oklab {
 pub struct Oklch {
     pub l: f64,
     pub c: f64,
-    pub h: f64,
+    pub h: Hue,
     pub d65_reference_l: bool,
 }
 
+/// Color difference formulas, for functions that can use any of them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Metric {
+    /// `Oklab::delta_E_ab`
+    Ab,
+    /// `Oklab::delta_E_Hyab`
+    Hyab,
+}
+
+impl Metric {
+    pub fn delta(self, first: Oklab, second: Oklab) -> f64 {
+        match self {
+            Self::Ab => first.delta_E_ab(second),
+            Self::Hyab => first.delta_E_Hyab(second),
+        }
+    }
+}
+
+/// Definitions of saturation, which is officially undefined in Oklab.
+///
+/// The common idea of saturation is chroma relative to lightness, which is `ChromaPerLightness`.
+/// The others treat saturation as chroma relative to "total perceived color sensation", or chroma and lightness combined in some way.
+/// Note that there isn't a definition for "relative lightness".
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SaturationModel {
+    /// `c / l`, unbounded near black.
+    ChromaPerLightness,
+    /// `c / delta_E_ab(BLACK)`, the sine of the angle from the lightness axis as seen from black. `delta_E_Hyab` might be similar.
+    ChromaPerDistanceFromBlack,
+    /// `c / (c + l)`, as used in `averaging.rs`.
+    ChromaPerChromaPlusLightness,
+    /// S of Okhsv, where `1.0` is the edge of the sRGB gamut between black and the most saturated color of the hue.
+    Okhsv,
+    /// S of Okhsl, where `1.0` is the edge of the sRGB gamut at the same lightness.
+    Okhsl,
+}
+
+/// CSS Color 4 hue interpolation methods, for going around the hue circle.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum HueInterpolation {
+    /// The arc of at most half a turn.
+    #[default]
+    Shorter,
+    /// The arc of at least half a turn.
+    Longer,
+    /// Counterclockwise, with the hue angle always going up.
+    Increasing,
+    /// Clockwise, with the hue angle always going down.
+    Decreasing,
+}
+
+impl HueInterpolation {
+    /// Hue angle between `from` and `to`, where `t = 0.0` is `from` and `t = 1.0` is `to`.
+    pub fn interpolate(self, from: Hue, to: Hue, t: f64) -> Hue {
+        let from = from.degrees();
+        let mut to = to.degrees();
+        let difference = to - from;
+        let adjustment = match self {
+            Self::Shorter if difference > 180.0 => -360.0,
+            Self::Shorter if difference < -180.0 => 360.0,
+            Self::Longer if 0.0 < difference && difference < 180.0 => -360.0,
+            Self::Longer if -180.0 < difference && difference <= 0.0 => 360.0,
+            Self::Increasing if difference < 0.0 => 360.0,
+            Self::Decreasing if difference > 0.0 => -360.0,
+            _ => 0.0,
+        };
+        to += adjustment;
+        Hue::from_degrees((to - from).mul_add(t, from))
+    }
+}
+
 impl std::fmt::Display for Oklab {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Oklab({}, {}, {})", self.l, self.a, self.b)
@@ -46,7 +123,7 @@ impl Default for Oklch {
         Self {
             l: 0.0,
             c: 0.0,
-            h: 0.0,
+            h: Hue::from_degrees(0.0),
             d65_reference_l: false,
         }
     }
@@ -103,25 +180,36 @@ impl Oklab {
         self.a.hypot(self.b)
     }
     /// Hue angle, where `0.0` is on the positive `self.a` axis, representing redness.
-    pub fn hue(self) -> f64 {
-        self.b.atan2(self.a)
+    pub fn hue(self) -> Hue {
+        Hue::from_radians(self.b.atan2(self.a))
     }
-    /// Officially undefined, the following is an interpretation of saturation.
-    ///
-    /// The common idea of saturation is chroma relative to lightness, so use `self.chroma() / self.l` if you think it's better.
-    ///
-    /// This interpretation is that saturation is chroma relative to "total perceived color sensation", or chroma and lightness combined in some way.
-    /// In this case, `self.delta_E_ab(Oklab::BLACK)` is considered to be chroma and lightness combined. However, `delta_E_Hyab` might be similar
-    /// Note that there isn't a definition for "relative lightness".
-    pub fn saturation(self) -> (f64, f64) {
-        (
-            self.chroma() / self.l,
-            self.chroma() / self.delta_E_ab(Self::BLACK),
-        )
+    /// Saturation under `model`, which is `0.0` for black, white and grays instead of dividing by zero.
+    /// Okhsv and Okhsl are computed from the unreferenced lightness, and only meaningful for colors in the sRGB gamut.
+    pub fn saturation(self, model: SaturationModel) -> f64 {
+        let chroma = self.chroma();
+        if chroma < Oklch::ACHROMATIC || self.l <= 0.0 {
+            return 0.0;
+        }
+        match model {
+            SaturationModel::ChromaPerLightness => chroma / self.l,
+            SaturationModel::ChromaPerDistanceFromBlack => chroma / self.delta_E_ab(Self::BLACK),
+            SaturationModel::ChromaPerChromaPlusLightness => chroma / (chroma + self.l),
+            SaturationModel::Okhsv | SaturationModel::Okhsl => {
+                let unreferenced = self.to_unreferenced_white();
+                let (a, b) = (self.a / chroma, self.b / chroma);
+                if model == SaturationModel::Okhsv {
+                    gamut::okhsv_saturation(unreferenced.l, chroma, a, b)
+                } else if unreferenced.l >= 1.0 {
+                    0.0
+                } else {
+                    gamut::okhsl_saturation(unreferenced.l, chroma, a, b)
+                }
+            }
+        }
     }
 
     /// Not to be confused with delta lowercase h, meaning difference in hue angles.
-    /// Use `self.hue() - other.hue()`, maybe with `.abs()`, instead.
+    /// Use `self.hue().difference(other.hue())`, maybe with `.abs()`, instead.
     ///
     /// Signed hue contribution delta.
     /// This is supposed to be the mean of the chroma cord lengths, if looking at the colors on a `self.a` vs `self.b` graph.
@@ -130,7 +218,8 @@ impl Oklab {
     #[allow(non_snake_case)]
     pub fn delta_H(self, other: Self) -> f64 {
         // DE94 formula
-        2.0 * (self.chroma() * other.chroma()).sqrt() * ((self.hue() - other.hue()) / 2.0).sin()
+        2.0 * (self.chroma() * other.chroma()).sqrt()
+            * (self.hue().difference(other.hue()).to_radians() / 2.0).sin()
     }
 
     /// Euclidian distance formula.
@@ -211,63 +300,152 @@ impl Oklab {
     pub fn to_srgb(self) -> rgb::sRGB {
         self.to_lrgb().to_srgb()
     }
-    /// Finds the sRGB value that is closest to the given Oklab. Very slow.
-    pub fn to_srgb_closest(self) -> rgb::sRGB {
-        // Early exit; should work
-        if (self.to_lrgb().min() >= -1e-6) && (self.to_lrgb().max() <= 1.0 + 1e-6) {
+    /// Whether the color fits in the sRGB gamut, give or take rounding errors.
+    pub fn in_gamut(self) -> bool {
+        let lrgb = self.to_lrgb();
+        (lrgb.min() >= -1e-6) && (lrgb.max() <= 1.0 + 1e-6)
+    }
+    /// CSS Color 4 gamut mapping: reduces chroma while keeping lightness and hue
+    /// until plain clipping is less than a just-noticeable difference away.
+    /// Much faster than `to_srgb_closest`, and never shifts the hue the way clipping does.
+    #[allow(clippy::while_float)] // bisection, the interval always halves
+    pub fn to_srgb_gamut_mapped(self) -> rgb::sRGB {
+        const JND: f64 = 0.02;
+        const EPSILON: f64 = 0.0001;
+
+        let origin = self.to_unreferenced_white().to_oklch();
+        if origin.l >= 1.0 {
+            return rgb::sRGB::new(255, 255, 255);
+        }
+        if origin.l <= 0.0 {
+            return rgb::sRGB::new(0, 0, 0);
+        }
+        if self.in_gamut() {
             return self.to_srgb();
         }
 
-        let saved_delta = Mutex::new(f64::MAX);
-        let saved_color = Mutex::new(rgb::sRGB { r: 0, g: 0, b: 0 });
+        let clip = |color: Self| color.to_lrgb().clamp().to_oklab();
+        let mut clipped = clip(self);
+        // Clipping un-references the lightness, so compare against the un-referenced color
+        if clipped.delta_E_ab(self.to_unreferenced_white()) < JND {
+            return clipped.to_srgb();
+        }
 
-        // Despite parallelization, this is still rather slow
-        rgb::sRGB::all_colors()
-            .par_bridge()
-            .map(super::rgb::sRGB::to_oklab)
-            .for_each(|sample| {
-                let delta = self.delta_E_ab(sample);
-                {
-                    let mut locked_saved_delta = saved_delta.lock();
-                    let mut locked_saved_color = saved_color.lock();
-
-                    if delta < *locked_saved_delta {
-                        *locked_saved_delta = delta;
-                        drop(locked_saved_delta);
-                        *locked_saved_color = sample.to_srgb();
-                        drop(locked_saved_color);
-                    }
+        let mut min = 0.0;
+        let mut max = origin.c;
+        let mut min_in_gamut = true;
+        while max - min > EPSILON {
+            let chroma = f64::midpoint(min, max);
+            let current = Oklch {
+                c: chroma,
+                ..origin
+            }
+            .to_oklab();
+            if min_in_gamut && current.in_gamut() {
+                min = chroma;
+                continue;
+            }
+            clipped = clip(current);
+            let delta = clipped.delta_E_ab(current);
+            if delta < JND {
+                if JND - delta < EPSILON {
+                    break;
                 }
-            });
+                min_in_gamut = false;
+                min = chroma;
+            } else {
+                max = chroma;
+            }
+        }
+        clipped.to_srgb()
+    }
 
-        saved_color.into_inner()
+    /// Linear interpolation, where `t = 0.0` is `self` and `t = 1.0` is `other`.
+    /// `other` is converted to the white reference of `self` first.
+    pub fn mix(self, other: Self, t: f64) -> Self {
+        let other = if self.d65_reference_l {
+            other.to_d65_white()
+        } else {
+            other.to_unreferenced_white()
+        };
+        Self {
+            l: (other.l - self.l).mul_add(t, self.l),
+            a: (other.a - self.a).mul_add(t, self.a),
+            b: (other.b - self.b).mul_add(t, self.b),
+            d65_reference_l: self.d65_reference_l,
+        }
+    }
+
+    /// Finds the sRGB value that is closest to the given Oklab under `delta_E_ab`. Very slow, as it scans the whole cube.
+    /// For many colors, query a `KdTree::cube` instead.
+    pub fn to_srgb_closest(self) -> rgb::sRGB {
+        // Early exit; should work
+        if self.in_gamut() {
+            return self.to_srgb();
+        }
+        (0..=u8::MAX)
+            .into_par_iter()
+            .flat_map_iter(|r| {
+                itertools::iproduct!(0..=u8::MAX, 0..=u8::MAX)
+                    .map(move |(g, b)| rgb::sRGB::new(r, g, b))
+            })
+            .map(|color| (self.delta_E_ab(color.to_oklab()), color))
+            .min_by(|first, second| first.0.total_cmp(&second.0).then(first.1.cmp(&second.1)))
+            .map_or_else(rgb::sRGB::default, |(_, color)| color)
     }
     /// Finds the sRGB value that is farthest away to the given Oklab.
+    ///
+    /// Only looks at the 8 one-bit colors, as the farthest color under `delta_E_Hyab` is always one of them.
+    /// Use `contrasting_colors` for other metrics or candidates.
     pub fn to_srgb_contrast(self) -> rgb::sRGB {
-        let saved_delta = Mutex::new(f64::MIN);
-        let saved_color = Mutex::new(rgb::sRGB { r: 0, g: 0, b: 0 });
-
-        // All of these colors are known to be the 1-bit values
-        itertools::iproduct!([0, 255], [0, 255], [0, 255])
-            .map(|(r, g, b)| rgb::sRGB { r, g, b })
+        self.contrasting_colors(Metric::Hyab, rgb::sRGB::corners(), |_| true, 1)
+            .first()
+            .map_or_else(rgb::sRGB::default, |(color, _)| *color)
+    }
+    /// Finds the `count` colors out of `candidates` that are farthest away from the given Oklab under `metric`,
+    /// farthest first and each with its delta.
+    ///
+    /// Candidates are skipped unless `filter` returns `true` for their Oklab value,
+    /// e.g. `|color| color.chroma() < 1e-6` for only grays or `|color| (0.4..0.6).contains(&color.l)` for a lightness range.
+    /// Ties are broken in favor of the lower sRGB value, so the output doesn't depend on thread timing.
+    pub fn contrasting_colors(
+        self,
+        metric: Metric,
+        candidates: impl Iterator<Item = rgb::sRGB> + Send,
+        filter: impl Fn(Self) -> bool + Sync,
+        count: usize,
+    ) -> Vec<(rgb::sRGB, f64)> {
+        candidates
             .par_bridge()
-            .map(super::rgb::sRGB::to_oklab)
-            .for_each(|sample| {
-                let delta = self.delta_E_Hyab(sample);
-                {
-                    let mut locked_saved_delta = saved_delta.lock();
-                    let mut locked_saved_color = saved_color.lock();
-
-                    if delta > *locked_saved_delta {
-                        *locked_saved_delta = delta;
-                        drop(locked_saved_delta);
-                        *locked_saved_color = sample.to_srgb();
-                        drop(locked_saved_color);
-                    }
+            .filter_map(|sample| {
+                let sample_oklab = sample.to_oklab();
+                filter(sample_oklab).then(|| (sample, metric.delta(self, sample_oklab)))
+            })
+            .fold(Vec::new, |mut best, scored| {
+                insert_top(&mut best, scored, count);
+                best
+            })
+            .reduce(Vec::new, |mut best, other| {
+                for scored in other {
+                    insert_top(&mut best, scored, count);
                 }
-            });
+                best
+            })
+    }
+}
 
-        saved_color.into_inner()
+/// Inserts `scored` into `best`, which is kept sorted farthest first and at most `count` long.
+fn insert_top(best: &mut Vec<(rgb::sRGB, f64)>, scored: (rgb::sRGB, f64), count: usize) {
+    let position = best.partition_point(|(color, delta)| {
+        delta
+            .total_cmp(&scored.1)
+            .reverse()
+            .then(color.cmp(&scored.0))
+            .is_lt()
+    });
+    if position < count {
+        best.insert(position, scored);
+        best.truncate(count);
     }
 }
 
@@ -275,8 +453,8 @@ impl Oklch {
     pub fn to_oklab(self) -> Oklab {
         Oklab {
             l: self.l,
-            a: self.c * self.h.cos(),
-            b: self.c * self.h.sin(),
+            a: self.c * self.h.radians().cos(),
+            b: self.c * self.h.radians().sin(),
             d65_reference_l: self.d65_reference_l,
         }
     }
@@ -287,6 +465,33 @@ impl Oklch {
     pub fn to_srgb_closest(self) -> rgb::sRGB {
         self.to_oklab().to_srgb_closest()
     }
+    pub fn to_srgb_gamut_mapped(self) -> rgb::sRGB {
+        self.to_oklab().to_srgb_gamut_mapped()
+    }
+
+    /// Below this chroma, the hue angle is meaningless, like the `89.87°` of sRGB grays.
+    pub const ACHROMATIC: f64 = 1e-6;
+
+    /// Polar interpolation, where `t = 0.0` is `self` and `t = 1.0` is `other`.
+    /// Like in CSS, an achromatic color takes the hue of the other color instead of its own meaningless one.
+    pub fn mix(self, other: Self, t: f64, method: HueInterpolation) -> Self {
+        let other = if self.d65_reference_l {
+            other.to_oklab().to_d65_white().to_oklch()
+        } else {
+            other.to_oklab().to_unreferenced_white().to_oklch()
+        };
+        let (from_hue, to_hue) = match (self.c < Self::ACHROMATIC, other.c < Self::ACHROMATIC) {
+            (true, false) => (other.h, other.h),
+            (false, true) => (self.h, self.h),
+            _ => (self.h, other.h),
+        };
+        Self {
+            l: (other.l - self.l).mul_add(t, self.l),
+            c: (other.c - self.c).mul_add(t, self.c),
+            h: method.interpolate(from_hue, to_hue, t),
+            d65_reference_l: self.d65_reference_l,
+        }
+    }
 }
 
 impl rgb::sRGB {
@@ -370,7 +575,7 @@ mod tests {
         assert!((test.l - 0.5).abs() < DIFFERENCE);
         assert!((test.a - 0.25).abs() < DIFFERENCE);
         assert!((test.b - 0.125).abs() < DIFFERENCE);
-        assert_eq!(test.d65_reference_l, false);
+        assert!(!test.d65_reference_l);
     }
 
     #[test]
@@ -386,7 +591,106 @@ mod tests {
         assert!((test.l - 0.5).abs() < DIFFERENCE);
         assert!((test.a - 0.25).abs() < DIFFERENCE);
         assert!((test.b - 0.125).abs() < DIFFERENCE);
-        assert_eq!(test.d65_reference_l, false);
+        assert!(!test.d65_reference_l);
+    }
+
+    #[test]
+    fn contrast_depends_on_metric() {
+        let black = oklab::Oklab::BLACK;
+        assert_eq!(black.to_srgb_contrast(), rgb::sRGB::new(255, 255, 0));
+        let (color, delta) =
+            black.contrasting_colors(oklab::Metric::Ab, rgb::sRGB::corners(), |_| true, 1)[0];
+        assert_eq!(color, rgb::sRGB::new(255, 255, 255));
+        assert!((delta - 1.0).abs() < DIFFERENCE);
+    }
+
+    #[test]
+    fn contrasting_colors_are_filtered_and_sorted() {
+        let gray = rgb::sRGB::new(128, 128, 128).to_oklab();
+        let top = gray.contrasting_colors(
+            oklab::Metric::Hyab,
+            rgb::sRGB::grays(),
+            |color| color.l < 0.5,
+            3,
+        );
+        assert_eq!(
+            top.iter().map(|(color, _)| *color).collect::<Vec<_>>(),
+            vec![
+                rgb::sRGB::new(0, 0, 0),
+                rgb::sRGB::new(1, 1, 1),
+                rgb::sRGB::new(2, 2, 2)
+            ]
+        );
+        assert!(top.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    }
+
+    #[test]
+    fn mix_endpoints() {
+        let first = rgb::sRGB::new(98, 0, 255).to_oklab();
+        let second = rgb::sRGB::new(255, 177, 0).to_oklab();
+        assert!(first.mix(second, 0.0).delta_E_ab(first) < DIFFERENCE);
+        assert!(first.mix(second, 1.0).delta_E_ab(second) < DIFFERENCE);
+        let middle = first.mix(second, 0.5);
+        assert!((middle.delta_E_ab(first) - middle.delta_E_ab(second)).abs() < DIFFERENCE);
+    }
+
+    #[test]
+    fn hue_interpolation_methods() {
+        let from = oklab::Hue::from_degrees(10.0);
+        let to = oklab::Hue::from_degrees(350.0);
+        let middle = |method: oklab::HueInterpolation| {
+            method
+                .interpolate(from, to, 0.5)
+                .difference(oklab::Hue::default())
+        };
+        assert!(middle(oklab::HueInterpolation::Shorter).abs() < DIFFERENCE);
+        assert!((middle(oklab::HueInterpolation::Longer) - 180.0).abs() < DIFFERENCE);
+        assert!((middle(oklab::HueInterpolation::Increasing) - 180.0).abs() < DIFFERENCE);
+        assert!(middle(oklab::HueInterpolation::Decreasing).abs() < DIFFERENCE);
+        // Same arcs, the other way around
+        let reversed = oklab::HueInterpolation::Increasing.interpolate(to, from, 0.5);
+        assert!(reversed.difference(oklab::Hue::default()).abs() < DIFFERENCE);
+    }
+
+    #[test]
+    fn achromatic_takes_other_hue() {
+        let gray = rgb::sRGB::new(128, 128, 128).to_oklch();
+        let red = rgb::sRGB::new(255, 0, 0).to_oklch();
+        let middle = gray.mix(red, 0.5, oklab::HueInterpolation::Shorter);
+        assert!(middle.h.difference(red.h).abs() < DIFFERENCE);
+    }
+
+    #[test]
+    fn gamut_mapping_keeps_hue() {
+        let in_gamut = rgb::sRGB::new(0, 197, 255);
+        assert_eq!(in_gamut.to_oklab().to_srgb_gamut_mapped(), in_gamut);
+
+        let out_of_gamut = oklab::Oklch {
+            l: 0.7,
+            c: 0.4,
+            h: oklab::Hue::from_degrees(143.0),
+            d65_reference_l: false,
+        };
+        let mapped = out_of_gamut.to_srgb_gamut_mapped().to_oklch();
+        assert!((mapped.l - out_of_gamut.l).abs() < 0.02);
+        assert!(mapped.h.difference(out_of_gamut.h).abs() < 3.0);
+        assert!(mapped.c < out_of_gamut.c);
+
+        // Just past the edge, the white reference of the input doesn't change the result
+        for (l, degrees, excess) in [(0.7, 0.0, 0.005), (0.7, 10.0, 0.005), (0.9, 5.0, 0.03)] {
+            let h = oklab::Hue::from_degrees(degrees);
+            let past = oklab::Oklch {
+                l,
+                c: oklab::gamut::max_chroma(l, h) + excess,
+                h,
+                d65_reference_l: false,
+            }
+            .to_oklab();
+            assert_eq!(
+                past.to_d65_white().to_srgb_gamut_mapped(),
+                past.to_srgb_gamut_mapped()
+            );
+        }
     }
 
     #[test]
@@ -411,4 +715,44 @@ mod tests {
         assert!((test.b - test_2.b).abs() < DIFFERENCE);
         assert_eq!(test.d65_reference_l, test_2.d65_reference_l);
     }
+
+    #[test]
+    fn saturation_models() {
+        use oklab::SaturationModel;
+        let models = [
+            SaturationModel::ChromaPerLightness,
+            SaturationModel::ChromaPerDistanceFromBlack,
+            SaturationModel::ChromaPerChromaPlusLightness,
+            SaturationModel::Okhsv,
+            SaturationModel::Okhsl,
+        ];
+        for model in models {
+            for gray in rgb::sRGB::grays().step_by(15) {
+                assert!(gray.to_oklab().saturation(model).abs() < f64::EPSILON);
+            }
+        }
+
+        let red = rgb::sRGB::new(255, 0, 0).to_oklab();
+        let (c, l) = (red.chroma(), red.l);
+        assert!((red.saturation(SaturationModel::ChromaPerLightness) - c / l).abs() < DIFFERENCE);
+        assert!(
+            (red.saturation(SaturationModel::ChromaPerDistanceFromBlack) - c / c.hypot(l)).abs()
+                < DIFFERENCE
+        );
+        assert!(
+            (red.saturation(SaturationModel::ChromaPerChromaPlusLightness) - c / (c + l)).abs()
+                < DIFFERENCE
+        );
+
+        // The most saturated colors of sRGB are at the edge of both Okhsv and Okhsl
+        for corner in rgb::sRGB::corners().filter(|&color| color.min() == 0 && color.max() == 255) {
+            let corner = corner.to_oklab();
+            assert!((corner.saturation(SaturationModel::Okhsv) - 1.0).abs() < 1e-3);
+            assert!((corner.saturation(SaturationModel::Okhsl) - 1.0).abs() < 1e-3);
+        }
+        // Still on the surface of the sRGB cube, but tinted towards white
+        let pink = rgb::sRGB::new(255, 128, 128).to_oklab();
+        assert!((0.3..0.7).contains(&pink.saturation(SaturationModel::Okhsv)));
+        assert!((pink.saturation(SaturationModel::Okhsl) - 1.0).abs() < 1e-3);
+    }
 }
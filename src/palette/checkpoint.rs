@@ -0,0 +1,279 @@
+use super::{Constraint, Generator, Palette};
+use crate::cvd;
+use crate::oklab::Metric;
+use crate::rgb::sRGB;
+use std::io;
+use std::path::Path;
+
+const HEADER: &str = "# contrasting_colors palette checkpoint";
+
+/// The settings of a `Generator` along with the colors it picked so far, saved as plain text so runs can be resumed.
+///
+/// Each line is a `key: value` pair, and keys for lists are repeated once per item:
+/// ```text
+/// count: 8
+/// metric: Hyab
+/// minimum_lightness: 0.5
+/// starting_color: #000000
+/// starting_color: #FFFFFF
+/// simulation: Deutan Machado 1
+/// background: #000000
+/// constraint: ContrastRatio 4.5
+/// color: #6200FF 0.7939182434924866
+/// ```
+/// Numbers are written with enough digits to be read back exactly, so a resumed run picks the same colors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub generator: Generator,
+    /// Picked colors, each with the delta it was picked with.
+    pub colors: Vec<(sRGB, f64)>,
+}
+
+/// A line of a checkpoint that couldn't be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Starting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Checkpoint {
+    pub fn new(generator: &Generator, palette: &Palette) -> Self {
+        Self {
+            generator: generator.clone(),
+            colors: palette
+                .colors
+                .iter()
+                .copied()
+                .zip(palette.deltas.iter().copied())
+                .collect(),
+        }
+    }
+
+    /// The picked colors, to be passed to `Generator::resume_tracked`.
+    pub fn palette(&self) -> Palette {
+        Palette {
+            colors: self.colors.iter().map(|&(color, _)| color).collect(),
+            deltas: self.colors.iter().map(|&(_, delta)| delta).collect(),
+            minimum_deltas: Vec::new(),
+        }
+    }
+
+    /// Writes to a temporary file first, so that being interrupted never leaves a half-written checkpoint behind.
+    ///
+    /// # Errors
+    /// Returns any error from writing or renaming the file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, self.to_string())?;
+        std::fs::rename(temporary, path)
+    }
+
+    /// # Errors
+    /// Returns any error from reading the file, or `io::ErrorKind::InvalidData` if it isn't a valid checkpoint.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        std::fs::read_to_string(path)?
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+impl std::fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let generator = &self.generator;
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "count: {}", generator.count)?;
+        writeln!(f, "metric: {:?}", generator.metric)?;
+        writeln!(f, "minimum_lightness: {}", generator.minimum_lightness)?;
+        for color in &generator.starting_colors {
+            writeln!(f, "starting_color: {}", color.to_hex())?;
+        }
+        for simulation in &generator.simulations {
+            writeln!(
+                f,
+                "simulation: {:?} {:?} {}",
+                simulation.deficiency, simulation.method, simulation.severity
+            )?;
+        }
+        for color in &generator.backgrounds {
+            writeln!(f, "background: {}", color.to_hex())?;
+        }
+        for constraint in &generator.constraints {
+            let (kind, minimum) = match constraint {
+                Constraint::ContrastRatio(minimum) => ("ContrastRatio", minimum),
+                Constraint::LightnessContrast(minimum) => ("LightnessContrast", minimum),
+                Constraint::LightnessDifference(minimum) => ("LightnessDifference", minimum),
+            };
+            writeln!(f, "constraint: {kind} {minimum}")?;
+        }
+        for (color, delta) in &self.colors {
+            writeln!(f, "color: {} {delta}", color.to_hex())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Checkpoint {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut checkpoint = Self {
+            generator: Generator {
+                starting_colors: Vec::new(),
+                ..Generator::default()
+            },
+            colors: Vec::new(),
+        };
+        let generator = &mut checkpoint.generator;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| ParseError {
+                line: index + 1,
+                message,
+            };
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| error(format!("expected `key: value`, found `{line}`")))?;
+            let mut words = value.split_whitespace();
+            let mut next_word = || {
+                words
+                    .next()
+                    .ok_or_else(|| error("missing value".to_string()))
+            };
+
+            match key.trim() {
+                "count" => generator.count = parse_number(next_word()?).map_err(error)?,
+                "metric" => generator.metric = parse_metric(next_word()?).map_err(error)?,
+                "minimum_lightness" => {
+                    generator.minimum_lightness = parse_number(next_word()?).map_err(error)?;
+                }
+                "starting_color" => generator
+                    .starting_colors
+                    .push(parse_color(next_word()?).map_err(error)?),
+                "background" => generator
+                    .backgrounds
+                    .push(parse_color(next_word()?).map_err(error)?),
+                "simulation" => generator.simulations.push(cvd::Simulation {
+                    deficiency: parse_deficiency(next_word()?).map_err(error)?,
+                    method: parse_method(next_word()?).map_err(error)?,
+                    severity: parse_number(next_word()?).map_err(error)?,
+                }),
+                "constraint" => generator
+                    .constraints
+                    .push(parse_constraint(next_word()?, next_word()?).map_err(error)?),
+                "color" => checkpoint.colors.push((
+                    parse_color(next_word()?).map_err(error)?,
+                    parse_number(next_word()?).map_err(error)?,
+                )),
+                other => return Err(error(format!("unknown key `{other}`"))),
+            }
+            if let Ok(extra) = next_word() {
+                return Err(error(format!("unexpected `{extra}`")));
+            }
+        }
+        Ok(checkpoint)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{value}` isn't a valid number"))
+}
+
+fn parse_color(value: &str) -> Result<sRGB, String> {
+    value
+        .parse()
+        .map_err(|error: crate::rgb::ParseColorError| error.to_string())
+}
+
+fn parse_metric(value: &str) -> Result<Metric, String> {
+    match value {
+        "Ab" => Ok(Metric::Ab),
+        "Hyab" => Ok(Metric::Hyab),
+        _ => Err(format!("unknown metric `{value}`")),
+    }
+}
+
+fn parse_deficiency(value: &str) -> Result<cvd::Deficiency, String> {
+    match value {
+        "Protan" => Ok(cvd::Deficiency::Protan),
+        "Deutan" => Ok(cvd::Deficiency::Deutan),
+        "Tritan" => Ok(cvd::Deficiency::Tritan),
+        _ => Err(format!("unknown deficiency `{value}`")),
+    }
+}
+
+fn parse_method(value: &str) -> Result<cvd::Method, String> {
+    match value {
+        "Brettel" => Ok(cvd::Method::Brettel),
+        "Vienot" => Ok(cvd::Method::Vienot),
+        "Machado" => Ok(cvd::Method::Machado),
+        _ => Err(format!("unknown simulation method `{value}`")),
+    }
+}
+
+fn parse_constraint(kind: &str, minimum: &str) -> Result<Constraint, String> {
+    let minimum = parse_number(minimum)?;
+    match kind {
+        "ContrastRatio" => Ok(Constraint::ContrastRatio(minimum)),
+        "LightnessContrast" => Ok(Constraint::LightnessContrast(minimum)),
+        "LightnessDifference" => Ok(Constraint::LightnessDifference(minimum)),
+        _ => Err(format!("unknown constraint `{kind}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cvd;
+    use crate::oklab;
+    use crate::palette;
+    use crate::palette::checkpoint::Checkpoint;
+    use crate::rgb;
+
+    #[test]
+    fn round_trip() {
+        let checkpoint = Checkpoint {
+            generator: palette::Generator {
+                count: 12,
+                metric: oklab::Metric::Ab,
+                minimum_lightness: 0.1,
+                simulations: vec![cvd::Simulation {
+                    deficiency: cvd::Deficiency::Tritan,
+                    method: cvd::Method::Brettel,
+                    severity: 0.7,
+                }],
+                backgrounds: vec![rgb::sRGB::new(32, 32, 32)],
+                constraints: vec![
+                    palette::Constraint::ContrastRatio(4.5),
+                    palette::Constraint::LightnessDifference(0.1),
+                ],
+                ..palette::Generator::default()
+            },
+            colors: vec![(rgb::sRGB::new(98, 0, 255), 0.793_918_243_492_486_6)],
+        };
+        assert_eq!(checkpoint.to_string().parse(), Ok(checkpoint));
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let error = "count: 8\n\nmetric: Lab".parse::<Checkpoint>().unwrap_err();
+        assert_eq!(error.line, 3);
+        assert!("color: #6200FF".parse::<Checkpoint>().is_err());
+        assert!("count: 8 9".parse::<Checkpoint>().is_err());
+        assert!("size: 8".parse::<Checkpoint>().is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use super::{beats, best_so_far, minimum_delta};
+use crate::oklab::{Metric, Oklab, SaturationModel};
+use crate::progress::Tracker;
+use crate::rgb::sRGB;
+use parking_lot::Mutex;
+use rayon::prelude::*;
+
+/// How far apart in hue the picked colors must be.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HueSpacing {
+    /// `180° / (n + 1)` with `n` colors already picked, so the spacing tightens as the palette grows.
+    Adaptive,
+    /// A constant spacing, in degrees.
+    Fixed(f64),
+}
+
+impl HueSpacing {
+    #[allow(clippy::cast_precision_loss)] // palettes are small
+    pub fn degrees(self, picked: usize) -> f64 {
+        match self {
+            Self::Adaptive => 180.0 / (picked + 1) as f64,
+            Self::Fixed(degrees) => degrees,
+        }
+    }
+}
+
+/// Greedy search for the most saturated colors that are spread out in hue.
+///
+/// Each round picks the candidate with the highest `saturation`, skipping candidates closer than `minimum_sensitivity`
+/// to black under `delta_E_ab`, or closer in hue to an already picked color than the `spacing` allows.
+/// The defaults are the settings of the search in `hue_diff.rs`.
+#[derive(Clone, Debug)]
+pub struct HueSpread {
+    pub count: usize,
+    /// Higher is better.
+    pub saturation: SaturationModel,
+    pub minimum_sensitivity: f64,
+    pub spacing: HueSpacing,
+    /// Only used for `Spread::minimum_delta`.
+    pub metric: Metric,
+}
+
+/// Colors in the order `HueSpread` picked them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Spread {
+    pub colors: Vec<sRGB>,
+    /// `HueSpread::saturation` of each color when it was picked.
+    pub saturations: Vec<f64>,
+    /// Smallest delta between any two colors under `HueSpread::metric`, or infinity if there are less than two.
+    pub minimum_delta: f64,
+}
+
+impl Default for HueSpread {
+    fn default() -> Self {
+        Self {
+            count: 8,
+            saturation: SaturationModel::ChromaPerDistanceFromBlack,
+            minimum_sensitivity: 2.0 / 3.0,
+            spacing: HueSpacing::Adaptive,
+            metric: Metric::Hyab,
+        }
+    }
+}
+
+impl HueSpread {
+    /// Runs every round over the whole sRGB cube.
+    pub fn generate(&self) -> Spread {
+        self.generate_from(&sRGB::all_colors())
+    }
+
+    /// Runs every round over `candidates` only.
+    /// Stops early if no candidate is left.
+    pub fn generate_from(
+        &self,
+        candidates: &(impl Iterator<Item = sRGB> + Clone + Send),
+    ) -> Spread {
+        self.generate_tracked(candidates, &Tracker::default())
+    }
+
+    /// Same as `generate_from`, reporting the progress of each round to `tracker`.
+    /// If cancelled, the best candidate of the unfinished round is still kept, and no more rounds are run.
+    pub fn generate_tracked(
+        &self,
+        candidates: &(impl Iterator<Item = sRGB> + Clone + Send),
+        tracker: &Tracker,
+    ) -> Spread {
+        let mut spread = Spread::default();
+
+        for _ in 0..self.count {
+            let Some((color, saturation)) =
+                self.pick_tracked(&spread.colors, candidates.clone(), tracker)
+            else {
+                break;
+            };
+            spread.colors.push(color);
+            spread.saturations.push(saturation);
+            if tracker.is_cancelled() {
+                break;
+            }
+        }
+
+        let seen = spread
+            .colors
+            .iter()
+            .map(|color| color.to_oklab())
+            .collect::<Vec<_>>();
+        spread.minimum_delta = minimum_delta(self.metric, &seen);
+        spread
+    }
+
+    /// A single round: the most saturated candidate that is far enough in hue from `saved_colors`, along with its saturation.
+    /// `None` if every candidate was rejected.
+    pub fn pick_from(
+        &self,
+        saved_colors: &[sRGB],
+        candidates: impl Iterator<Item = sRGB> + Send,
+    ) -> Option<(sRGB, f64)> {
+        self.pick_tracked(saved_colors, candidates, &Tracker::default())
+    }
+
+    /// Same as `pick_from`, reporting progress to `tracker`.
+    /// If cancelled, only the candidates checked so far are considered.
+    pub fn pick_tracked(
+        &self,
+        saved_colors: &[sRGB],
+        candidates: impl Iterator<Item = sRGB> + Send,
+        tracker: &Tracker,
+    ) -> Option<(sRGB, f64)> {
+        let saved_hues = saved_colors
+            .iter()
+            .map(|color| color.to_oklch().h)
+            .collect::<Vec<_>>();
+        let spacing = self.spacing.degrees(saved_colors.len());
+
+        let saved_saturation = Mutex::new(f64::NEG_INFINITY);
+        let saved_color = Mutex::new(None);
+        let run = tracker.start(&candidates);
+
+        candidates
+            .par_bridge()
+            .take_any_while(|_| !tracker.is_cancelled())
+            .inspect(|_| run.step(|| best_so_far(&saved_color, &saved_saturation)))
+            .for_each(|test_color| {
+                let test_oklab = test_color.to_oklab();
+                if test_oklab.delta_E_ab(Oklab::BLACK) < self.minimum_sensitivity
+                    || saved_colors.contains(&test_color)
+                {
+                    return;
+                }
+                let test_hue = test_oklab.hue();
+                if saved_hues
+                    .iter()
+                    .any(|hue| test_hue.difference(*hue).abs() < spacing)
+                {
+                    return;
+                }
+
+                let saturation = test_oklab.saturation(self.saturation);
+
+                let mut locked_saved_saturation = saved_saturation.lock();
+                let mut locked_saved_color = saved_color.lock();
+
+                if beats(
+                    saturation,
+                    test_color,
+                    *locked_saved_saturation,
+                    *locked_saved_color,
+                ) {
+                    *locked_saved_saturation = saturation;
+                    drop(locked_saved_saturation);
+                    *locked_saved_color = Some(test_color);
+                    drop(locked_saved_color);
+                }
+            });
+
+        saved_color
+            .into_inner()
+            .map(|color| (color, saved_saturation.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oklab;
+    use crate::palette::hue_spread;
+    use crate::palette::tests::coarse_cube;
+
+    #[test]
+    fn hue_spread_keeps_spacing() {
+        let generator = hue_spread::HueSpread {
+            count: 4,
+            ..hue_spread::HueSpread::default()
+        };
+        let spread = generator.generate_from(&coarse_cube());
+        assert_eq!(spread.colors.len(), 4);
+        assert_eq!(spread.saturations.len(), 4);
+        assert!(spread.minimum_delta.is_finite());
+        for (index, color) in spread.colors.iter().enumerate() {
+            let hue = color.to_oklch().h;
+            for earlier in &spread.colors[..index] {
+                let spacing = hue_spread::HueSpacing::Adaptive.degrees(index);
+                assert!(hue.difference(earlier.to_oklch().h).abs() >= spacing);
+            }
+            assert!(
+                color.to_oklab().delta_E_ab(oklab::Oklab::BLACK) >= generator.minimum_sensitivity
+            );
+        }
+    }
+
+    #[test]
+    fn hue_spread_fixed_spacing_runs_out() {
+        let spread = hue_spread::HueSpread {
+            spacing: hue_spread::HueSpacing::Fixed(120.0),
+            saturation: oklab::SaturationModel::ChromaPerLightness,
+            ..hue_spread::HueSpread::default()
+        }
+        .generate_from(&coarse_cube());
+        assert!(spread.colors.len() <= 3);
+        assert!(spread.saturations.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+}
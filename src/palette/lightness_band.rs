@@ -0,0 +1,159 @@
+use super::beats;
+use crate::oklab::{Hue, Oklch, SaturationModel};
+use crate::rgb::sRGB;
+use rayon::prelude::*;
+
+/// Equally wide hue buckets, starting at `start` and going towards increasing hues.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HueRange {
+    pub start: Hue,
+    /// In degrees, `360.0` for the whole circle.
+    pub width: f64,
+    pub buckets: usize,
+}
+
+impl Default for HueRange {
+    fn default() -> Self {
+        Self {
+            start: Hue::default(),
+            width: 360.0,
+            buckets: 12,
+        }
+    }
+}
+
+impl HueRange {
+    #[allow(clippy::cast_precision_loss)] // bucket counts are small
+    pub fn bucket_width(self) -> f64 {
+        self.width.min(360.0) / self.buckets as f64
+    }
+
+    /// Index of the bucket `hue` falls in, `None` if outside of the range.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // clamped to the buckets
+    pub fn bucket(self, hue: Hue) -> Option<usize> {
+        let offset = Hue::from_degrees(hue.degrees() - self.start.degrees()).degrees();
+        if self.buckets == 0 || (self.width < 360.0 && offset > self.width) {
+            return None;
+        }
+        Some(((offset / self.bucket_width()) as usize).min(self.buckets - 1))
+    }
+
+    /// Hues at the start and end of bucket `index`.
+    #[allow(clippy::cast_precision_loss)] // bucket counts are small
+    pub fn bounds(self, index: usize) -> (Hue, Hue) {
+        let width = self.bucket_width();
+        (
+            Hue::from_degrees(width.mul_add(index as f64, self.start.degrees())),
+            Hue::from_degrees(width.mul_add((index + 1) as f64, self.start.degrees())),
+        )
+    }
+}
+
+/// The most saturated color in each bucket of `hue_range` whose `Oklab::l` is within `margin` of `target_l`,
+/// along with its saturation, so every hue can be had at the same lightness.
+/// Buckets without any such color are `None`, and grays are never picked as their hue is meaningless.
+/// Searches the whole sRGB cube.
+pub fn most_saturated_at_lightness(
+    target_l: f64,
+    margin: f64,
+    saturation: SaturationModel,
+    hue_range: HueRange,
+) -> Vec<Option<(sRGB, f64)>> {
+    most_saturated_at_lightness_from(sRGB::all_colors(), target_l, margin, saturation, hue_range)
+}
+
+/// Same as `most_saturated_at_lightness`, searching `candidates` only.
+pub fn most_saturated_at_lightness_from(
+    candidates: impl Iterator<Item = sRGB> + Send,
+    target_l: f64,
+    margin: f64,
+    saturation: SaturationModel,
+    hue_range: HueRange,
+) -> Vec<Option<(sRGB, f64)>> {
+    let keep_better = |best: &mut Option<(sRGB, f64)>, color: sRGB, score: f64| {
+        if beats(
+            score,
+            color,
+            best.map_or(f64::NEG_INFINITY, |(_, score)| score),
+            best.map(|(color, _)| color),
+        ) {
+            *best = Some((color, score));
+        }
+    };
+
+    candidates
+        .par_bridge()
+        .fold(
+            || vec![None; hue_range.buckets],
+            |mut best, color| {
+                let oklab = color.to_oklab();
+                if (oklab.l - target_l).abs() > margin || oklab.chroma() < Oklch::ACHROMATIC {
+                    return best;
+                }
+                if let Some(bucket) = hue_range.bucket(oklab.hue()) {
+                    keep_better(&mut best[bucket], color, oklab.saturation(saturation));
+                }
+                best
+            },
+        )
+        .reduce(
+            || vec![None; hue_range.buckets],
+            |mut first, second| {
+                for (best, other) in first.iter_mut().zip(second) {
+                    if let Some((color, score)) = other {
+                        keep_better(best, color, score);
+                    }
+                }
+                first
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oklab;
+    use crate::palette::lightness_band;
+    use crate::palette::tests::coarse_cube;
+
+    #[test]
+    fn lightness_band_per_hue() {
+        let saturation = oklab::SaturationModel::ChromaPerDistanceFromBlack;
+        let hue_range = lightness_band::HueRange::default();
+        let best = lightness_band::most_saturated_at_lightness_from(
+            coarse_cube(),
+            0.7,
+            0.05,
+            saturation,
+            hue_range,
+        );
+        assert_eq!(best.len(), 12);
+        assert!(best.iter().all(Option::is_some));
+
+        for (bucket, &(color, score)) in best.iter().flatten().enumerate() {
+            let oklab = color.to_oklab();
+            assert!((oklab.l - 0.7).abs() <= 0.05);
+            assert_eq!(hue_range.bucket(oklab.hue()), Some(bucket));
+            // Nothing else in the band and the bucket is more saturated
+            assert!(coarse_cube().all(|other| {
+                let other = other.to_oklab();
+                (other.l - 0.7).abs() > 0.05
+                    || hue_range.bucket(other.hue()) != Some(bucket)
+                    || other.saturation(saturation) <= score
+            }));
+        }
+    }
+
+    #[test]
+    fn hue_range_wraps_around() {
+        let hue_range = lightness_band::HueRange {
+            start: oklab::Hue::from_degrees(330.0),
+            width: 60.0,
+            buckets: 2,
+        };
+        assert_eq!(hue_range.bucket(oklab::Hue::from_degrees(340.0)), Some(0));
+        assert_eq!(hue_range.bucket(oklab::Hue::from_degrees(10.0)), Some(1));
+        assert_eq!(hue_range.bucket(oklab::Hue::from_degrees(30.0)), Some(1));
+        assert_eq!(hue_range.bucket(oklab::Hue::from_degrees(90.0)), None);
+        assert_eq!(hue_range.bounds(1).0, oklab::Hue::from_degrees(0.0));
+    }
+}
@@ -0,0 +1,463 @@
+#![allow(dead_code)]
+
+pub mod checkpoint;
+pub mod hue_spread;
+pub mod lightness_band;
+pub mod refine;
+pub mod report;
+
+use crate::contrast;
+use crate::cvd;
+use crate::oklab::{Metric, Oklab};
+use crate::progress::Tracker;
+use crate::rgb::sRGB;
+use parking_lot::Mutex;
+use rayon::prelude::*;
+
+/// A minimum contrast that a candidate must have against every background.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Constraint {
+    /// WCAG 2.x contrast ratio, from `1.0` to `21.0`.
+    ContrastRatio(f64),
+    /// Magnitude of APCA Lc, with the candidate as text drawn on the background.
+    LightnessContrast(f64),
+    /// Absolute difference in `Oklab::l`.
+    LightnessDifference(f64),
+}
+
+impl Constraint {
+    pub fn is_satisfied(self, candidate: sRGB, background: sRGB) -> bool {
+        match self {
+            Self::ContrastRatio(minimum) => {
+                contrast::contrast_ratio(candidate.to_lrgb(), background.to_lrgb()) >= minimum
+            }
+            Self::LightnessContrast(minimum) => {
+                contrast::apca::lightness_contrast(candidate, background).abs() >= minimum
+            }
+            Self::LightnessDifference(minimum) => {
+                (candidate.to_oklab().l - background.to_oklab().l).abs() >= minimum
+            }
+        }
+    }
+}
+
+/// Normal color vision, or a simulated deficiency.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Vision {
+    Normal,
+    Simulated(cvd::Simulation),
+}
+
+impl Vision {
+    /// How `color` looks with this kind of vision.
+    pub fn see(self, color: sRGB) -> Oklab {
+        match self {
+            Self::Normal => color.to_oklab(),
+            Self::Simulated(simulation) => color.to_lrgb().simulate_cvd(simulation).to_oklab(),
+        }
+    }
+}
+
+impl std::fmt::Display for Vision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Normal => write!(f, "Normal"),
+            Self::Simulated(simulation) => write!(
+                f,
+                "{:?} ({:?}, {:.2})",
+                simulation.deficiency, simulation.method, simulation.severity
+            ),
+        }
+    }
+}
+
+/// Whether a candidate beats the best so far, breaking ties in favor of the lower sRGB value
+/// so that the result doesn't depend on the order the threads get to the candidates.
+fn beats(score: f64, color: sRGB, best_score: f64, best_color: Option<sRGB>) -> bool {
+    score
+        .total_cmp(&best_score)
+        .then_with(|| best_color.map_or(std::cmp::Ordering::Greater, |best| best.cmp(&color)))
+        .is_gt()
+}
+
+/// The current best of a search, locked in the same order as the search itself to avoid deadlocks.
+fn best_so_far(saved_color: &Mutex<Option<sRGB>>, saved_score: &Mutex<f64>) -> Option<(sRGB, f64)> {
+    let locked_saved_score = saved_score.lock();
+    let best = saved_color.lock().map(|color| (color, *locked_saved_score));
+    drop(locked_saved_score);
+    best
+}
+
+/// Smallest delta between any two of `colors`, or infinity if there are less than two.
+fn minimum_delta(metric: Metric, colors: &[Oklab]) -> f64 {
+    colors
+        .iter()
+        .enumerate()
+        .flat_map(|(index, first)| {
+            colors[index + 1..]
+                .iter()
+                .map(|second| metric.delta(*first, *second))
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Greedy farthest-point palette search over the sRGB cube.
+///
+/// Each round picks the color that maximizes the smallest delta between any two colors chosen so far.
+/// With `simulations`, that smallest delta is also taken over how the colors look with each deficiency,
+/// so the palette stays distinguishable for colorblind players.
+/// The defaults are the settings of the original search in `main.rs`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Generator {
+    /// Colors that the palette has to be distinguishable from, but which aren't part of the output.
+    pub starting_colors: Vec<sRGB>,
+    pub count: usize,
+    pub metric: Metric,
+    /// Deficiencies to score candidates under, in addition to normal vision.
+    pub simulations: Vec<cvd::Simulation>,
+    /// Candidates darker than this `Oklab::l` are skipped.
+    pub minimum_lightness: f64,
+    /// Every candidate must satisfy every constraint against every background.
+    pub backgrounds: Vec<sRGB>,
+    pub constraints: Vec<Constraint>,
+}
+
+/// Colors in the order they were picked, each with the smallest delta of the palette at the time.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Palette {
+    pub colors: Vec<sRGB>,
+    pub deltas: Vec<f64>,
+    /// Smallest delta between any two colors of the final palette, including the starting colors, for each vision.
+    pub minimum_deltas: Vec<(Vision, f64)>,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self {
+            starting_colors: vec![sRGB::new(0, 0, 0), sRGB::new(255, 255, 255)],
+            count: 8,
+            metric: Metric::Hyab,
+            simulations: Vec::new(),
+            minimum_lightness: 0.5,
+            backgrounds: Vec::new(),
+            constraints: Vec::new(),
+        }
+    }
+}
+
+impl Generator {
+    /// Normal vision, followed by every simulation.
+    pub fn visions(&self) -> Vec<Vision> {
+        std::iter::once(Vision::Normal)
+            .chain(self.simulations.iter().copied().map(Vision::Simulated))
+            .collect()
+    }
+
+    /// Smallest delta between any two of `colors` for each vision.
+    pub fn minimum_deltas(&self, colors: &[sRGB]) -> Vec<(Vision, f64)> {
+        self.visions()
+            .into_iter()
+            .map(|vision| {
+                let seen = colors
+                    .iter()
+                    .map(|&color| vision.see(color))
+                    .collect::<Vec<_>>();
+                (vision, minimum_delta(self.metric, &seen))
+            })
+            .collect()
+    }
+
+    /// The objective of the search: the smallest delta between any two of `colors` over all visions.
+    pub fn score(&self, colors: &[sRGB]) -> f64 {
+        self.minimum_deltas(colors)
+            .into_iter()
+            .map(|(_, delta)| delta)
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Smallest delta between `color` and any of `others` over all visions.
+    pub fn nearest_delta(&self, color: sRGB, others: &[sRGB]) -> f64 {
+        self.visions()
+            .into_iter()
+            .flat_map(|vision| {
+                let seen = vision.see(color);
+                others
+                    .iter()
+                    .map(move |&other| self.metric.delta(seen, vision.see(other)))
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Whether `candidate` is allowed at all, checked before any scoring.
+    pub fn accepts(&self, candidate: sRGB) -> bool {
+        candidate.to_oklab().l >= self.minimum_lightness
+            && self.backgrounds.iter().all(|&background| {
+                self.constraints
+                    .iter()
+                    .all(|constraint| constraint.is_satisfied(candidate, background))
+            })
+    }
+
+    /// Runs every round over the whole sRGB cube.
+    pub fn generate(&self) -> Palette {
+        self.generate_from(&sRGB::all_colors())
+    }
+
+    /// Runs every round over `candidates` only.
+    /// Stops early if no candidate is left.
+    pub fn generate_from(
+        &self,
+        candidates: &(impl Iterator<Item = sRGB> + Clone + Send),
+    ) -> Palette {
+        self.generate_tracked(candidates, &Tracker::default())
+    }
+
+    /// Same as `generate_from`, reporting the progress of each round to `tracker`.
+    /// If cancelled, the best candidate of the unfinished round is still kept, and no more rounds are run.
+    pub fn generate_tracked(
+        &self,
+        candidates: &(impl Iterator<Item = sRGB> + Clone + Send),
+        tracker: &Tracker,
+    ) -> Palette {
+        self.resume_tracked(Palette::default(), candidates, tracker, |_| {})
+    }
+
+    /// Runs the rounds that are left after `palette`, such as the one from a `checkpoint::Checkpoint`.
+    /// `on_pick` is called after every finished round, which is where checkpoints should be written,
+    /// but not for the unfinished round of a cancelled run, as its pick might not be the best one.
+    pub fn resume_tracked(
+        &self,
+        mut palette: Palette,
+        candidates: &(impl Iterator<Item = sRGB> + Clone + Send),
+        tracker: &Tracker,
+        mut on_pick: impl FnMut(&Palette),
+    ) -> Palette {
+        let mut saved_colors = self.starting_colors.clone();
+        saved_colors.extend(&palette.colors);
+
+        while palette.colors.len() < self.count {
+            let Some((color, delta)) =
+                self.pick_tracked(&saved_colors, candidates.clone(), tracker)
+            else {
+                break;
+            };
+            saved_colors.push(color);
+            palette.colors.push(color);
+            palette.deltas.push(delta);
+            if tracker.is_cancelled() {
+                break;
+            }
+            on_pick(&palette);
+        }
+        palette.minimum_deltas = self.minimum_deltas(&saved_colors);
+        palette
+    }
+
+    /// A single round over the whole sRGB cube.
+    pub fn pick(&self, saved_colors: &[sRGB]) -> Option<(sRGB, f64)> {
+        self.pick_from(saved_colors, sRGB::all_colors())
+    }
+
+    /// A single round: the candidate farthest from `saved_colors`, along with the smallest delta of the palette including it.
+    /// `None` if every candidate was rejected.
+    pub fn pick_from(
+        &self,
+        saved_colors: &[sRGB],
+        candidates: impl Iterator<Item = sRGB> + Send,
+    ) -> Option<(sRGB, f64)> {
+        self.pick_tracked(saved_colors, candidates, &Tracker::default())
+    }
+
+    /// Same as `pick_from`, reporting progress to `tracker`.
+    /// If cancelled, only the candidates checked so far are considered.
+    pub fn pick_tracked(
+        &self,
+        saved_colors: &[sRGB],
+        candidates: impl Iterator<Item = sRGB> + Send,
+        tracker: &Tracker,
+    ) -> Option<(sRGB, f64)> {
+        let visions = self.visions();
+        let saved_seen = visions
+            .iter()
+            .map(|&vision| {
+                saved_colors
+                    .iter()
+                    .map(|&color| vision.see(color))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        // Doesn't depend on the candidate, so only compute it once
+        let saved_minimum = saved_seen
+            .iter()
+            .map(|seen| minimum_delta(self.metric, seen))
+            .fold(f64::INFINITY, f64::min);
+
+        let saved_delta = Mutex::new(f64::NEG_INFINITY);
+        let saved_color = Mutex::new(None);
+        let run = tracker.start(&candidates);
+
+        candidates
+            .par_bridge()
+            .take_any_while(|_| !tracker.is_cancelled())
+            .inspect(|_| run.step(|| best_so_far(&saved_color, &saved_delta)))
+            .for_each(|test_srgb| {
+                if saved_colors.contains(&test_srgb) || !self.accepts(test_srgb) {
+                    return;
+                }
+                // TODO: find a good averaging method
+                let delta = visions
+                    .iter()
+                    .zip(&saved_seen)
+                    .flat_map(|(&vision, seen)| {
+                        let test_seen = vision.see(test_srgb);
+                        seen.iter()
+                            .map(move |color| self.metric.delta(*color, test_seen))
+                    })
+                    .fold(saved_minimum, f64::min);
+
+                let mut locked_saved_delta = saved_delta.lock();
+                let mut locked_saved_color = saved_color.lock();
+
+                if beats(delta, test_srgb, *locked_saved_delta, *locked_saved_color) {
+                    *locked_saved_delta = delta;
+                    drop(locked_saved_delta);
+                    *locked_saved_color = Some(test_srgb);
+                    drop(locked_saved_color);
+                }
+            });
+
+        saved_color
+            .into_inner()
+            .map(|color| (color, saved_delta.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contrast;
+    use crate::cvd;
+    use crate::palette;
+    use crate::progress;
+    use crate::rgb;
+
+    /// Every 17th value per channel, small enough to search in debug builds.
+    pub(super) fn coarse_cube() -> impl Iterator<Item = rgb::sRGB> + Clone + Send {
+        itertools::iproduct!(0..=15, 0..=15, 0..=15)
+            .map(|(r, g, b): (u8, u8, u8)| rgb::sRGB::new(r * 17, g * 17, b * 17))
+    }
+
+    #[test]
+    fn deltas_never_increase() {
+        let palette = palette::Generator {
+            count: 4,
+            ..palette::Generator::default()
+        }
+        .generate_from(&coarse_cube());
+        assert_eq!(palette.colors.len(), 4);
+        assert!(palette.deltas.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+
+    #[test]
+    fn ties_ignore_candidate_order() {
+        let generator = palette::Generator::default();
+        let candidates = coarse_cube().collect::<Vec<_>>();
+        let forwards =
+            generator.pick_from(&generator.starting_colors, candidates.clone().into_iter());
+        let backwards =
+            generator.pick_from(&generator.starting_colors, candidates.into_iter().rev());
+        assert_eq!(forwards, backwards);
+
+        let (red, blue) = (rgb::sRGB::new(255, 0, 0), rgb::sRGB::new(0, 0, 255));
+        assert!(palette::beats(0.5, blue, 0.5, Some(red)));
+        assert!(!palette::beats(0.5, red, 0.5, Some(blue)));
+        assert!(palette::beats(0.5, red, f64::NEG_INFINITY, None));
+    }
+
+    #[test]
+    fn resuming_matches_one_run() {
+        let generator = palette::Generator {
+            count: 4,
+            ..palette::Generator::default()
+        };
+        let tracker = progress::Tracker::default();
+        let whole = generator.generate_from(&coarse_cube());
+
+        let mut checkpoints = Vec::new();
+        generator.resume_tracked(
+            palette::Palette::default(),
+            &coarse_cube(),
+            &tracker,
+            |palette| {
+                checkpoints
+                    .push(palette::checkpoint::Checkpoint::new(&generator, palette).to_string());
+            },
+        );
+        assert_eq!(checkpoints.len(), 4);
+
+        let checkpoint = checkpoints[1]
+            .parse::<palette::checkpoint::Checkpoint>()
+            .unwrap();
+        assert_eq!(checkpoint.generator, generator);
+        let resumed = checkpoint.generator.resume_tracked(
+            checkpoint.palette(),
+            &coarse_cube(),
+            &tracker,
+            |_| {},
+        );
+        assert_eq!(resumed, whole);
+    }
+
+    #[test]
+    fn simulations_are_scored() {
+        let generator = palette::Generator {
+            count: 3,
+            simulations: vec![
+                cvd::Simulation::dichromacy(cvd::Deficiency::Protan),
+                cvd::Simulation::dichromacy(cvd::Deficiency::Deutan),
+            ],
+            ..palette::Generator::default()
+        };
+        let palette = generator.generate_from(&coarse_cube());
+        assert_eq!(palette.minimum_deltas.len(), 3);
+        assert_eq!(palette.minimum_deltas[0].0, palette::Vision::Normal);
+
+        // The last pick was scored by the worst of all visions
+        let worst = palette
+            .minimum_deltas
+            .iter()
+            .map(|(_, delta)| *delta)
+            .fold(f64::INFINITY, f64::min);
+        assert!((palette.deltas[2] - worst).abs() < 1e-12);
+    }
+
+    #[test]
+    fn constraints_reject_candidates() {
+        let black = rgb::sRGB::new(0, 0, 0);
+        let generator = palette::Generator {
+            count: 4,
+            backgrounds: vec![black],
+            constraints: vec![
+                palette::Constraint::ContrastRatio(10.0),
+                palette::Constraint::LightnessContrast(60.0),
+            ],
+            ..palette::Generator::default()
+        };
+        let palette = generator.generate_from(&coarse_cube());
+        assert!(!palette.colors.is_empty());
+        for color in palette.colors {
+            assert!(contrast::contrast_ratio(color.to_lrgb(), black.to_lrgb()) >= 10.0);
+            assert!(contrast::apca::lightness_contrast(color, black).abs() >= 60.0);
+        }
+    }
+
+    #[test]
+    fn impossible_constraints_stop_early() {
+        let palette = palette::Generator {
+            backgrounds: vec![rgb::sRGB::new(128, 128, 128)],
+            constraints: vec![palette::Constraint::ContrastRatio(21.0)],
+            ..palette::Generator::default()
+        }
+        .generate_from(&coarse_cube());
+        assert!(palette.colors.is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+use super::{Generator, Palette};
+use crate::rgb::sRGB;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Number of entries in `Refinement::trace` for runs with at least that many iterations.
+const TRACE_LENGTH: usize = 100;
+
+/// Simulated annealing over the sRGB cube, to improve on the choices that a greedy search locks in early.
+///
+/// Each iteration either nudges one color by up to `max_step` per channel, shrinking as the temperature drops,
+/// or, with `jump_probability`, replaces it with a random color.
+/// Moves that make the palette worse are still accepted sometimes, more rarely as the temperature drops,
+/// so the search can get out of local optima.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Annealing {
+    pub seed: u64,
+    pub iterations: usize,
+    /// Must be positive.
+    pub initial_temperature: f64,
+    /// Must not be negative, where `0.0` ends the run accepting only improvements.
+    pub final_temperature: f64,
+    pub max_step: u8,
+    /// From `0.0` to `1.0`.
+    pub jump_probability: f64,
+}
+
+/// The refined palette, and how the search got there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Refinement {
+    /// `Palette::deltas` are the smallest delta from each color to any other, including the starting colors.
+    pub palette: Palette,
+    /// `Generator::score` of the palette before and after.
+    pub initial_score: f64,
+    pub score: f64,
+    pub accepted: usize,
+    pub improvements: usize,
+    /// Iteration of the last improvement of the best score, where a late one hints that more iterations would help.
+    pub last_improvement: usize,
+    /// Best score so far, recorded 100 times evenly over the run, or after every iteration for shorter runs.
+    pub trace: Vec<f64>,
+}
+
+impl Default for Annealing {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            iterations: 20_000,
+            initial_temperature: 0.01,
+            final_temperature: 0.000_01,
+            max_step: 64,
+            jump_probability: 0.05,
+        }
+    }
+}
+
+impl Annealing {
+    /// Refines `colors` (without the starting colors) under the same objective, metric, visions and constraints as `generator`.
+    /// Colors that `generator` wouldn't accept are never moved to.
+    ///
+    /// # Panics
+    /// Panics if `initial_temperature` isn't positive, `final_temperature` is negative,
+    /// or `jump_probability` is outside of `0.0` to `1.0`.
+    #[allow(clippy::cast_precision_loss)] // iteration counts are far below 2^52
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn refine(&self, generator: &Generator, colors: &[sRGB]) -> Refinement {
+        assert!(
+            self.initial_temperature > 0.0,
+            "Initial temperature must be positive"
+        );
+        assert!(
+            self.final_temperature >= 0.0,
+            "Final temperature must not be negative"
+        );
+        assert!(
+            (0.0..=1.0).contains(&self.jump_probability),
+            "Jump probability must be from 0 to 1"
+        );
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let fixed = generator.starting_colors.len();
+
+        let mut current = generator.starting_colors.clone();
+        current.extend_from_slice(colors);
+        let mut current_score = generator.score(&current);
+        let initial_score = current_score;
+        let mut best = current.clone();
+        let mut best_score = current_score;
+
+        let mut accepted = 0;
+        let mut improvements = 0;
+        let mut last_improvement = 0;
+        let mut trace = Vec::with_capacity(TRACE_LENGTH.min(self.iterations));
+
+        for iteration in 0..self.iterations {
+            let progress = iteration as f64 / self.iterations as f64;
+            let temperature = self.initial_temperature
+                * (self.final_temperature / self.initial_temperature).powf(progress);
+            let step = (f64::from(self.max_step) * (1.0 - progress))
+                .ceil()
+                .max(1.0) as i16;
+
+            if !colors.is_empty() {
+                let index = fixed + rng.random_range(0..colors.len());
+                let candidate = if rng.random_bool(self.jump_probability) {
+                    sRGB::new(rng.random(), rng.random(), rng.random())
+                } else {
+                    let mut nudge = |channel: u8| {
+                        (i16::from(channel) + rng.random_range(-step..=step)).clamp(0, 255) as u8
+                    };
+                    let color = current[index];
+                    sRGB::new(nudge(color.r), nudge(color.g), nudge(color.b))
+                };
+
+                if !current.contains(&candidate) && generator.accepts(candidate) {
+                    let previous = std::mem::replace(&mut current[index], candidate);
+                    let score = generator.score(&current);
+                    if score >= current_score
+                        || rng.random_bool(((score - current_score) / temperature).exp())
+                    {
+                        accepted += 1;
+                        current_score = score;
+                        if score > best_score {
+                            best_score = score;
+                            best.clone_from(&current);
+                            improvements += 1;
+                            last_improvement = iteration;
+                        }
+                    } else {
+                        current[index] = previous;
+                    }
+                }
+            }
+
+            // Whenever the run crosses the next of `TRACE_LENGTH` equal parts
+            if (iteration + 1) * TRACE_LENGTH / self.iterations
+                > iteration * TRACE_LENGTH / self.iterations
+            {
+                trace.push(best_score);
+            }
+        }
+
+        let deltas = (fixed..best.len())
+            .map(|index| {
+                let others = best
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != index)
+                    .map(|(_, color)| *color)
+                    .collect::<Vec<_>>();
+                generator.nearest_delta(best[index], &others)
+            })
+            .collect();
+        Refinement {
+            palette: Palette {
+                colors: best[fixed..].to_vec(),
+                deltas,
+                minimum_deltas: generator.minimum_deltas(&best),
+            },
+            initial_score,
+            score: best_score,
+            accepted,
+            improvements,
+            last_improvement,
+            trace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette;
+    use crate::palette::refine;
+    use crate::palette::tests::coarse_cube;
+
+    #[test]
+    fn refinement_never_gets_worse() {
+        let generator = palette::Generator {
+            count: 4,
+            ..palette::Generator::default()
+        };
+        let greedy = generator.generate_from(&coarse_cube());
+        let annealing = refine::Annealing {
+            iterations: 2000,
+            seed: 7,
+            ..refine::Annealing::default()
+        };
+        let refinement = annealing.refine(&generator, &greedy.colors);
+
+        assert_eq!(refinement.palette.colors.len(), 4);
+        assert!(refinement.score >= refinement.initial_score);
+        assert!((refinement.initial_score - greedy.deltas[3]).abs() < 1e-12);
+        assert_eq!(refinement.trace.len(), 100);
+        assert!(refinement.trace.windows(2).all(|pair| pair[0] <= pair[1]));
+        for color in &refinement.palette.colors {
+            assert!(generator.accepts(*color));
+        }
+
+        // Same seed, same result
+        assert_eq!(annealing.refine(&generator, &greedy.colors), refinement);
+
+        // Short and uneven runs
+        for (iterations, length) in [(30, 30), (150, 100), (250, 100)] {
+            let refinement = refine::Annealing {
+                iterations,
+                ..annealing
+            }
+            .refine(&generator, &greedy.colors);
+            assert_eq!(refinement.trace.len(), length);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Initial temperature must be positive")]
+    fn zero_initial_temperature_panics() {
+        refine::Annealing {
+            initial_temperature: 0.0,
+            ..refine::Annealing::default()
+        }
+        .refine(&palette::Generator::default(), &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Jump probability must be from 0 to 1")]
+    fn jump_probability_out_of_range_panics() {
+        refine::Annealing {
+            jump_probability: 1.5,
+            ..refine::Annealing::default()
+        }
+        .refine(&palette::Generator::default(), &[]);
+    }
+}
@@ -0,0 +1,379 @@
+use super::lightness_band::HueRange;
+use super::{Generator, Vision};
+use crate::contrast;
+use crate::cvd;
+use crate::oklab::{Metric, Oklch};
+use crate::rgb::sRGB;
+use itertools::Itertools;
+use std::io;
+use std::path::Path;
+
+/// What to measure about a palette in a `Report`.
+/// The defaults cover both metrics, every dichromacy, and black and white backgrounds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Analysis {
+    pub metrics: Vec<Metric>,
+    /// Deficiencies to take minimum distances under, in addition to normal vision.
+    pub simulations: Vec<cvd::Simulation>,
+    pub backgrounds: Vec<sRGB>,
+    /// Buckets for `Report::hue_counts`.
+    pub hue_range: HueRange,
+}
+
+impl Default for Analysis {
+    fn default() -> Self {
+        Self {
+            metrics: vec![Metric::Ab, Metric::Hyab],
+            simulations: [
+                cvd::Deficiency::Protan,
+                cvd::Deficiency::Deutan,
+                cvd::Deficiency::Tritan,
+            ]
+            .map(cvd::Simulation::dichromacy)
+            .to_vec(),
+            backgrounds: vec![sRGB::new(0, 0, 0), sRGB::new(255, 255, 255)],
+            hue_range: HueRange::default(),
+        }
+    }
+}
+
+/// Pairs of colors are given as indices into `Report::colors`, the lower one first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distances {
+    pub metric: Metric,
+    /// Symmetric, with zeros on the diagonal.
+    pub matrix: Vec<Vec<f64>>,
+    /// `None` for less than two colors.
+    pub closest_pair: Option<(usize, usize, f64)>,
+    /// Smallest distance between any two colors for normal vision, then for each simulation.
+    pub minimum_deltas: Vec<(Vision, f64)>,
+}
+
+/// Contrast of every color of the palette against one background.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundContrast {
+    pub background: sRGB,
+    /// WCAG 2.x contrast ratios.
+    pub contrast_ratios: Vec<f64>,
+    /// APCA Lc, with the color as text drawn on the background.
+    pub lightness_contrasts: Vec<f64>,
+}
+
+/// One-shot evaluation of any palette, printed with `Display`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub colors: Vec<sRGB>,
+    pub distances: Vec<Distances>,
+    /// `Oklab::l` of each color.
+    pub lightnesses: Vec<f64>,
+    /// The two colors closest in lightness, `None` for less than two colors.
+    pub smallest_lightness_gap: Option<(usize, usize, f64)>,
+    pub hue_range: HueRange,
+    /// Number of colors in each bucket of `hue_range`, leaving out grays.
+    pub hue_counts: Vec<usize>,
+    /// The widest arc without any color, in degrees counterclockwise from the first color of the pair to the second.
+    /// Leaves out grays, and is `None` if every color is gray.
+    pub largest_hue_gap: Option<(usize, usize, f64)>,
+    pub contrasts: Vec<BackgroundContrast>,
+}
+
+impl Analysis {
+    pub fn analyze(&self, colors: &[sRGB]) -> Report {
+        let distances = self
+            .metrics
+            .iter()
+            .map(|&metric| {
+                let matrix = colors
+                    .iter()
+                    .map(|first| {
+                        colors
+                            .iter()
+                            .map(|second| metric.delta(first.to_oklab(), second.to_oklab()))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                let closest_pair = (0..colors.len())
+                    .tuple_combinations()
+                    .map(|(first, second)| (first, second, matrix[first][second]))
+                    .min_by(|first, second| first.2.total_cmp(&second.2));
+                let generator = Generator {
+                    metric,
+                    simulations: self.simulations.clone(),
+                    ..Generator::default()
+                };
+                Distances {
+                    metric,
+                    matrix,
+                    closest_pair,
+                    minimum_deltas: generator.minimum_deltas(colors),
+                }
+            })
+            .collect();
+
+        let lightnesses = colors
+            .iter()
+            .map(|color| color.to_oklab().l)
+            .collect::<Vec<_>>();
+        let smallest_lightness_gap = (0..colors.len())
+            .tuple_combinations()
+            .map(|(first, second)| {
+                (
+                    first,
+                    second,
+                    (lightnesses[first] - lightnesses[second]).abs(),
+                )
+            })
+            .min_by(|first, second| first.2.total_cmp(&second.2));
+
+        let chromatic = colors
+            .iter()
+            .map(|color| color.to_oklch())
+            .enumerate()
+            .filter(|(_, color)| color.c >= Oklch::ACHROMATIC)
+            .sorted_by(|first, second| first.1.h.degrees().total_cmp(&second.1.h.degrees()))
+            .collect::<Vec<_>>();
+        let mut hue_counts = vec![0; self.hue_range.buckets];
+        for (_, color) in &chromatic {
+            if let Some(bucket) = self.hue_range.bucket(color.h) {
+                hue_counts[bucket] += 1;
+            }
+        }
+        // Each color to the next one counterclockwise, where only the last one wraps around to the first,
+        // so a single color has a gap of the whole circle and colors of the same hue have none between them
+        let largest_hue_gap = chromatic
+            .iter()
+            .zip(chromatic.iter().cycle().skip(1))
+            .enumerate()
+            .map(|(index, ((first, from), (second, to)))| {
+                let gap = to.h.degrees() - from.h.degrees();
+                let wraps = index + 1 == chromatic.len();
+                (*first, *second, if wraps { gap + 360.0 } else { gap })
+            })
+            .max_by(|first, second| first.2.total_cmp(&second.2));
+
+        let contrasts = self
+            .backgrounds
+            .iter()
+            .map(|&background| BackgroundContrast {
+                background,
+                contrast_ratios: colors
+                    .iter()
+                    .map(|color| contrast::contrast_ratio(color.to_lrgb(), background.to_lrgb()))
+                    .collect(),
+                lightness_contrasts: colors
+                    .iter()
+                    .map(|&color| contrast::apca::lightness_contrast(color, background))
+                    .collect(),
+            })
+            .collect();
+
+        Report {
+            colors: colors.to_vec(),
+            distances,
+            lightnesses,
+            smallest_lightness_gap,
+            hue_range: self.hue_range,
+            hue_counts,
+            largest_hue_gap,
+            contrasts,
+        }
+    }
+}
+
+impl Report {
+    /// Fraction of the buckets of `hue_range` with at least one color.
+    #[allow(clippy::cast_precision_loss)] // bucket counts are small
+    pub fn hue_coverage(&self) -> f64 {
+        if self.hue_counts.is_empty() {
+            return 0.0;
+        }
+        self.hue_counts.iter().filter(|&&count| count > 0).count() as f64
+            / self.hue_counts.len() as f64
+    }
+
+    /// Every pair of colors on a line, with the distance under each metric.
+    ///
+    /// # Errors
+    /// Returns any error from writing the file.
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, PairsCsv(self).to_string())
+    }
+}
+
+/// CSV with a line per pair of colors and a column per metric.
+struct PairsCsv<'a>(&'a Report);
+
+impl std::fmt::Display for PairsCsv<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let report = self.0;
+        write!(f, "first,second")?;
+        for distances in &report.distances {
+            write!(f, ",{:?}", distances.metric)?;
+        }
+        writeln!(f)?;
+        for (first, second) in (0..report.colors.len()).tuple_combinations() {
+            write!(
+                f,
+                "{},{}",
+                report.colors[first].to_hex(),
+                report.colors[second].to_hex()
+            )?;
+            for distances in &report.distances {
+                write!(f, ",{}", distances.matrix[first][second])?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex = |index: usize| self.colors[index].to_hex();
+
+        for distances in &self.distances {
+            writeln!(f, "{:?} distances:", distances.metric)?;
+            for (index, row) in distances.matrix.iter().enumerate() {
+                write!(f, "  {}", hex(index))?;
+                for delta in row {
+                    write!(f, " {delta:.3}")?;
+                }
+                writeln!(f)?;
+            }
+            if let Some((first, second, delta)) = distances.closest_pair {
+                writeln!(
+                    f,
+                    "  Closest pair: {} and {} at {delta:.5}",
+                    hex(first),
+                    hex(second)
+                )?;
+            }
+            for (vision, delta) in &distances.minimum_deltas {
+                writeln!(f, "  {vision}: {delta:.5}")?;
+            }
+        }
+
+        write!(f, "Lightness:")?;
+        for (index, l) in self.lightnesses.iter().enumerate() {
+            write!(f, " {} {l:.3}", hex(index))?;
+        }
+        writeln!(f)?;
+        if let Some((first, second, gap)) = self.smallest_lightness_gap {
+            writeln!(
+                f,
+                "  Closest lightnesses: {} and {} at {gap:.5}",
+                hex(first),
+                hex(second)
+            )?;
+        }
+
+        writeln!(
+            f,
+            "Hue coverage: {:.0}% of {} buckets of {:.1}°",
+            self.hue_coverage() * 100.0,
+            self.hue_counts.len(),
+            self.hue_range.bucket_width()
+        )?;
+        for (index, count) in self.hue_counts.iter().enumerate() {
+            let (start, end) = self.hue_range.bounds(index);
+            writeln!(f, "  {start} to {end}: {count}")?;
+        }
+        if let Some((first, second, gap)) = self.largest_hue_gap {
+            writeln!(
+                f,
+                "  Largest gap: {gap:.1}° from {} to {}",
+                hex(first),
+                hex(second)
+            )?;
+        }
+
+        writeln!(f, "Nearest named colors:")?;
+        for &color in &self.colors {
+            let (name, delta) = color.nearest_named(Metric::Hyab);
+            writeln!(f, "  {} {name} at {delta:.5}", color.to_hex())?;
+        }
+
+        for contrast in &self.contrasts {
+            writeln!(f, "Contrast against {}:", contrast.background.to_hex())?;
+            for (index, (ratio, lc)) in contrast
+                .contrast_ratios
+                .iter()
+                .zip(&contrast.lightness_contrasts)
+                .enumerate()
+            {
+                writeln!(f, "  {} {ratio:.2}:1, Lc {lc:.1}", hex(index))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oklab;
+    use crate::palette::report;
+    use crate::rgb;
+
+    #[test]
+    fn primaries() {
+        let colors = [
+            rgb::sRGB::new(255, 0, 0),
+            rgb::sRGB::new(0, 255, 0),
+            rgb::sRGB::new(0, 0, 255),
+            rgb::sRGB::new(0, 0, 0),
+        ];
+        let report = report::Analysis::default().analyze(&colors);
+
+        let ab = &report.distances[0];
+        assert_eq!(ab.metric, oklab::Metric::Ab);
+        for (first, row) in ab.matrix.iter().enumerate() {
+            assert!(row[first].abs() < f64::EPSILON);
+            for (second, delta) in row.iter().enumerate() {
+                assert!((delta - ab.matrix[second][first]).abs() < f64::EPSILON);
+            }
+        }
+        // Red and green, at about 0.52, are just closer than blue and black
+        let (first, second, delta) = ab.closest_pair.unwrap();
+        assert_eq!((first, second), (0, 1));
+        assert!((delta - ab.minimum_deltas[0].1).abs() < f64::EPSILON);
+        assert_eq!(ab.minimum_deltas.len(), 4);
+
+        // Black has no hue, and the three primaries are in 3 different buckets
+        assert_eq!(report.hue_counts.iter().sum::<usize>(), 3);
+        assert!((report.hue_coverage() - 0.25).abs() < f64::EPSILON);
+        // Blue to red, through magenta, is the widest arc at about 125°
+        let (from, to, gap) = report.largest_hue_gap.unwrap();
+        assert_eq!((from, to), (2, 0));
+        assert!(gap > 120.0);
+
+        let on_white = &report.contrasts[1];
+        assert_eq!(on_white.background, rgb::sRGB::new(255, 255, 255));
+        assert!((on_white.contrast_ratios[3] - 21.0).abs() < 1e-9);
+        assert!(on_white.lightness_contrasts[3] > 100.0);
+        assert_eq!(report.to_string().lines().next(), Some("Ab distances:"));
+    }
+
+    #[test]
+    fn single_color() {
+        let report = report::Analysis::default().analyze(&[rgb::sRGB::new(255, 128, 0)]);
+        assert!(report.distances[0].closest_pair.is_none());
+        assert!(report.smallest_lightness_gap.is_none());
+        let (from, to, gap) = report.largest_hue_gap.unwrap();
+        assert_eq!((from, to), (0, 0));
+        assert!((gap - 360.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn duplicate_hues() {
+        let (red, blue) = (rgb::sRGB::new(255, 0, 0), rgb::sRGB::new(0, 0, 255));
+        let report = report::Analysis::default().analyze(&[red, red, blue]);
+        // Red to blue counterclockwise is about 235°, blue back to red through magenta the rest
+        let (from, to, gap) = report.largest_hue_gap.unwrap();
+        assert_eq!((report.colors[from], to), (red, 2));
+        assert!((230.0..240.0).contains(&gap));
+
+        let report = report::Analysis::default().analyze(&[red, red]);
+        let (_, _, gap) = report.largest_hue_gap.unwrap();
+        assert!((gap - 360.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,204 @@
+#![allow(dead_code)]
+
+use crate::rgb::sRGB;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// A snapshot of a running search, handed to `Tracker::report`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Progress {
+    pub checked: usize,
+    /// `None` if the number of candidates isn't known up front.
+    pub total: Option<usize>,
+    /// The best candidate so far, along with its score.
+    pub best: Option<(sRGB, f64)>,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    #[allow(clippy::cast_precision_loss)] // at most 2^24 candidates
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .filter(|&total| total > 0)
+            .map(|total| self.checked as f64 / total as f64)
+    }
+
+    /// Time left, assuming the remaining candidates take as long as the ones checked so far.
+    pub fn eta(&self) -> Option<Duration> {
+        let fraction = self.fraction().filter(|&fraction| fraction > 0.0)?;
+        Some(self.elapsed.mul_f64((1.0 - fraction).max(0.0) / fraction))
+    }
+}
+
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.fraction() {
+            Some(fraction) => write!(f, "{:5.1}%", fraction * 100.0)?,
+            None => write!(f, "{} checked", self.checked)?,
+        }
+        if let Some((color, score)) = self.best {
+            write!(f, ", best {color} at {score:.5}")?;
+        }
+        if let Some(eta) = self.eta() {
+            write!(f, ", ETA {:.1}s", eta.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+/// Flag for stopping a search early.
+/// Clones share the flag, so one can be moved into another thread such as a Ctrl-C handler.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Progress reporting and cancellation for the searches over the sRGB cube.
+/// A cancelled search stops handing out candidates and returns the best of the ones it already checked.
+/// The default never reports and is never cancelled.
+pub struct Tracker<'a> {
+    pub cancellation: Cancellation,
+    /// Number of candidates between two calls to `report`.
+    pub interval: usize,
+    pub report: &'a (dyn Fn(&Progress) + Sync),
+}
+
+impl Default for Tracker<'_> {
+    fn default() -> Self {
+        Self {
+            cancellation: Cancellation::default(),
+            interval: 1 << 20,
+            report: &|_| {},
+        }
+    }
+}
+
+impl Tracker<'_> {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Starts counting a single search over `candidates`, which is only used for its size hint.
+    pub fn start(&self, candidates: &impl Iterator) -> Run<'_> {
+        let total = match candidates.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        };
+        Run {
+            tracker: self,
+            total,
+            checked: AtomicUsize::new(0),
+            start: Instant::now(),
+        }
+    }
+}
+
+/// Counter for a single search, shared between its threads.
+pub struct Run<'a> {
+    tracker: &'a Tracker<'a>,
+    total: Option<usize>,
+    checked: AtomicUsize,
+    start: Instant,
+}
+
+impl Run<'_> {
+    /// Counts one more candidate, reporting every `Tracker::interval` candidates.
+    /// `best` is only called when reporting, as it usually has to lock something.
+    pub fn step(&self, best: impl FnOnce() -> Option<(sRGB, f64)>) {
+        let checked = self.checked.fetch_add(1, Ordering::Relaxed) + 1;
+        if checked.is_multiple_of(self.tracker.interval.max(1)) {
+            (self.tracker.report)(&Progress {
+                checked,
+                total: self.total,
+                best: best(),
+                elapsed: self.start.elapsed(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette;
+    use crate::progress;
+    use crate::rgb;
+    use std::time::Duration;
+
+    /// Every 17th value per channel, small enough to search in debug builds.
+    fn coarse_cube() -> impl Iterator<Item = rgb::sRGB> + Clone + Send {
+        itertools::iproduct!(0..=15, 0..=15, 0..=15)
+            .map(|(r, g, b): (u8, u8, u8)| rgb::sRGB::new(r * 17, g * 17, b * 17))
+    }
+
+    #[test]
+    fn eta_scales_with_elapsed_time() {
+        let progress = progress::Progress {
+            checked: 25,
+            total: Some(100),
+            best: None,
+            elapsed: Duration::from_secs(10),
+        };
+        assert_eq!(progress.fraction(), Some(0.25));
+        assert_eq!(progress.eta(), Some(Duration::from_secs(30)));
+
+        let unknown = progress::Progress {
+            total: None,
+            ..progress
+        };
+        assert_eq!(unknown.eta(), None);
+        assert_eq!(unknown.to_string(), "25 checked");
+    }
+
+    #[test]
+    fn reports_every_interval() {
+        let reports = parking_lot::Mutex::new(Vec::new());
+        let report = |progress: &progress::Progress| reports.lock().push(*progress);
+        let tracker = progress::Tracker {
+            interval: 10,
+            report: &report,
+            ..progress::Tracker::default()
+        };
+
+        let candidates = rgb::sRGB::grays();
+        let run = tracker.start(&candidates);
+        candidates.for_each(|color| run.step(|| Some((color, 0.0))));
+
+        let reports = reports.into_inner();
+        assert_eq!(reports.len(), 25);
+        assert_eq!(reports[0].checked, 10);
+        assert_eq!(reports[0].total, Some(256));
+        assert_eq!(reports[0].best, Some((rgb::sRGB::new(9, 9, 9), 0.0)));
+    }
+
+    #[test]
+    fn cancellation_keeps_best_so_far() {
+        let generator = palette::Generator::default();
+        let cancellation = progress::Cancellation::default();
+        let report = |_: &progress::Progress| cancellation.cancel();
+        let tracker = progress::Tracker {
+            cancellation: cancellation.clone(),
+            interval: 2000,
+            report: &report,
+        };
+
+        let palette = generator.generate_tracked(&coarse_cube(), &tracker);
+        assert!(tracker.is_cancelled());
+        assert_eq!(palette.colors.len(), 1);
+        assert!(generator.accepts(palette.colors[0]));
+
+        // Nothing was checked at all
+        assert_eq!(
+            generator.pick_tracked(&generator.starting_colors, coarse_cube(), &tracker),
+            None
+        );
+    }
+}
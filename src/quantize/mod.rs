@@ -0,0 +1,370 @@
+#![allow(dead_code)]
+
+use crate::oklab::Oklab;
+use crate::oklab::kd_tree::KdTree;
+use crate::render::Canvas;
+use crate::rgb;
+use rayon::prelude::*;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+/// How the error between a pixel and its palette color is spread out, so that areas average to the right color.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Dithering {
+    /// Every pixel becomes its nearest palette color, which bands on gradients.
+    #[default]
+    None,
+    /// Pushes the error of each pixel onto the next ones, which keeps the average the closest but spreads errors far.
+    FloydSteinberg,
+    /// Offsets the lightness of each pixel by an 8×8 Bayer threshold map of this amplitude, in the units of the `DiffusionSpace`:
+    /// `l` in Oklab, and all three channels alike in linear light.
+    /// Around the distance between neighboring palette colors works best, such as `0.1` for Oklab.
+    /// Unlike error diffusion, each pixel only depends on itself, so it stays put when the image changes elsewhere.
+    Ordered(f64),
+}
+
+/// Where pixels are averaged while dithering.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum DiffusionSpace {
+    /// Physically correct, so areas have the same light output as the original.
+    LinearLight,
+    /// Perceptually even, so errors are spread by how visible they are.
+    #[default]
+    Oklab,
+}
+
+impl DiffusionSpace {
+    fn coordinates(self, color: rgb::sRGB) -> [f64; 3] {
+        match self {
+            Self::LinearLight => {
+                let color = color.to_lrgb();
+                [color.r, color.g, color.b]
+            }
+            Self::Oklab => {
+                let color = color.to_oklab();
+                [color.l, color.a, color.b]
+            }
+        }
+    }
+
+    /// Moves `coordinates` along the lightness axis only, as offsetting `a` and `b` would push colors toward warm or cool.
+    fn lighten(self, [first, second, third]: [f64; 3], offset: f64) -> [f64; 3] {
+        match self {
+            Self::LinearLight => [first + offset, second + offset, third + offset],
+            Self::Oklab => [first + offset, second, third],
+        }
+    }
+
+    /// Coordinates can be out of gamut after adding errors, which still have a nearest palette color.
+    fn to_oklab(self, [first, second, third]: [f64; 3]) -> Oklab {
+        match self {
+            Self::LinearLight => rgb::lRGB {
+                r: first,
+                g: second,
+                b: third,
+            }
+            .to_oklab(),
+            Self::Oklab => Oklab {
+                l: first,
+                a: second,
+                b: third,
+                d65_reference_l: false,
+            },
+        }
+    }
+}
+
+/// Settings for reducing an image to a palette, each pixel taking the nearest palette color by `delta_E_ab`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Quantization {
+    pub dithering: Dithering,
+    pub space: DiffusionSpace,
+}
+
+/// An image made of indices into a palette of at most 256 colors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Indexed {
+    pub width: usize,
+    pub height: usize,
+    pub palette: Vec<rgb::sRGB>,
+    /// Row by row, from the top-left corner.
+    pub indices: Vec<u8>,
+}
+
+impl Quantization {
+    /// # Panics
+    /// Panics if `palette` is empty or has more than 256 colors.
+    pub fn quantize(&self, canvas: &Canvas, palette: &[rgb::sRGB]) -> Indexed {
+        assert!(
+            (1..=256).contains(&palette.len()),
+            "Palette must have 1 to 256 colors"
+        );
+        let tree = KdTree::new(
+            palette
+                .iter()
+                .map(|color| color.to_oklab())
+                .zip(0..=u8::MAX),
+        );
+        let nearest = |coordinates| {
+            tree.nearest(self.space.to_oklab(coordinates))
+                .map_or(0, |(index, _)| index)
+        };
+        let palette_coordinates = palette
+            .iter()
+            .map(|&color| self.space.coordinates(color))
+            .collect::<Vec<_>>();
+
+        let pixels = canvas.pixels();
+        let indices = match self.dithering {
+            Dithering::None => pixels
+                .par_iter()
+                .map(|&color| nearest(self.space.coordinates(color)))
+                .collect(),
+            Dithering::Ordered(amplitude) => pixels
+                .par_iter()
+                .enumerate()
+                .map(|(index, &color)| {
+                    let offset = amplitude * threshold(index % canvas.width, index / canvas.width);
+                    nearest(self.space.lighten(self.space.coordinates(color), offset))
+                })
+                .collect(),
+            Dithering::FloydSteinberg => {
+                let mut coordinates = pixels
+                    .par_iter()
+                    .map(|&color| self.space.coordinates(color))
+                    .collect::<Vec<_>>();
+                let mut indices = Vec::with_capacity(pixels.len());
+                for y in 0..canvas.height {
+                    for x in 0..canvas.width {
+                        let current = coordinates[y * canvas.width + x];
+                        let index = nearest(current);
+                        indices.push(index);
+                        let picked = palette_coordinates[usize::from(index)];
+                        let error = [0, 1, 2].map(|channel| current[channel] - picked[channel]);
+
+                        let mut diffuse = |x: Option<usize>, y: usize, weight: f64| {
+                            if let Some(x) = x.filter(|&x| x < canvas.width)
+                                && y < canvas.height
+                            {
+                                let target = &mut coordinates[y * canvas.width + x];
+                                for channel in 0..3 {
+                                    target[channel] =
+                                        error[channel].mul_add(weight, target[channel]);
+                                }
+                            }
+                        };
+                        diffuse(Some(x + 1), y, 7.0 / 16.0);
+                        diffuse(x.checked_sub(1), y + 1, 3.0 / 16.0);
+                        diffuse(Some(x), y + 1, 5.0 / 16.0);
+                        diffuse(Some(x + 1), y + 1, 1.0 / 16.0);
+                    }
+                }
+                indices
+            }
+        };
+
+        Indexed {
+            width: canvas.width,
+            height: canvas.height,
+            palette: palette.to_vec(),
+            indices,
+        }
+    }
+}
+
+/// Entry of an 8×8 Bayer matrix, spread from `-0.5` to `0.5`.
+/// The index is made of the bits of `x ^ y` and `y`, interleaved and reversed.
+#[allow(clippy::cast_precision_loss)] // at most 63
+fn threshold(x: usize, y: usize) -> f64 {
+    let (x, y) = (x % 8, y % 8);
+    let xor = x ^ y;
+    let index = ((xor & 1) << 5)
+        | ((y & 1) << 4)
+        | ((xor & 2) << 2)
+        | ((y & 2) << 1)
+        | ((xor & 4) >> 1)
+        | ((y & 4) >> 2);
+    (index as f64 + 0.5) / 64.0 - 0.5
+}
+
+impl Indexed {
+    pub fn to_canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height, rgb::sRGB::default());
+        for (position, &index) in self.indices.iter().enumerate() {
+            let color = self.palette[usize::from(index)];
+            canvas.fill_rect(position % self.width, position / self.width, 1, 1, color);
+        }
+        canvas
+    }
+
+    /// Writes an 8-bit indexed PNG with `palette` as its palette.
+    ///
+    /// # Errors
+    /// Returns any error from creating the file or encoding the PNG.
+    pub fn write_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(file),
+            u32::try_from(self.width).expect("Image too wide for a PNG"),
+            u32::try_from(self.height).expect("Image too tall for a PNG"),
+        );
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(
+            self.palette
+                .iter()
+                .flat_map(|color| [color.r, color.g, color.b])
+                .collect::<Vec<u8>>(),
+        );
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.indices)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::quantize;
+    use crate::render;
+    use crate::rgb;
+
+    const BLACK_AND_WHITE: [rgb::sRGB; 2] =
+        [rgb::sRGB::new(0, 0, 0), rgb::sRGB::new(255, 255, 255)];
+
+    /// Share of white pixels after dithering a flat color to black and white.
+    #[allow(clippy::cast_precision_loss)] // small images
+    fn white_share(color: rgb::sRGB, quantization: quantize::Quantization) -> f64 {
+        let canvas = render::Canvas::new(64, 64, color);
+        let indexed = quantization.quantize(&canvas, &BLACK_AND_WHITE);
+        // White is index 1, black is 0
+        indexed
+            .indices
+            .iter()
+            .map(|&index| usize::from(index))
+            .sum::<usize>() as f64
+            / indexed.indices.len() as f64
+    }
+
+    #[test]
+    fn palette_colors_stay_put() {
+        let palette = [
+            rgb::sRGB::new(255, 0, 0),
+            rgb::sRGB::new(0, 128, 255),
+            rgb::sRGB::new(32, 32, 32),
+        ];
+        let mut canvas = render::Canvas::new(3, 3, palette[2]);
+        canvas.fill_rect(0, 0, 3, 1, palette[0]);
+        canvas.fill_rect(1, 1, 1, 2, palette[1]);
+        for dithering in [
+            quantize::Dithering::None,
+            quantize::Dithering::FloydSteinberg,
+        ] {
+            let indexed = quantize::Quantization {
+                dithering,
+                ..Default::default()
+            }
+            .quantize(&canvas, &palette);
+            assert_eq!(indexed.indices, [0, 0, 0, 2, 1, 2, 2, 1, 2]);
+            assert_eq!(indexed.to_canvas(), canvas);
+        }
+    }
+
+    #[test]
+    fn dithering_keeps_the_average() {
+        // Half of white's light output
+        let gray = rgb::lRGB {
+            r: 0.5,
+            g: 0.5,
+            b: 0.5,
+        }
+        .to_srgb();
+        let linear = white_share(
+            gray,
+            quantize::Quantization {
+                dithering: quantize::Dithering::FloydSteinberg,
+                space: quantize::DiffusionSpace::LinearLight,
+            },
+        );
+        assert!((linear - 0.5).abs() < 0.02);
+
+        // Half of white's Oklab lightness instead
+        let gray = rgb::sRGB::new(99, 99, 99);
+        let oklab = white_share(
+            gray,
+            quantize::Quantization {
+                dithering: quantize::Dithering::FloydSteinberg,
+                space: quantize::DiffusionSpace::Oklab,
+            },
+        );
+        assert!((oklab - gray.to_oklab().l).abs() < 0.02);
+
+        let ordered = white_share(
+            gray,
+            quantize::Quantization {
+                dithering: quantize::Dithering::Ordered(1.0),
+                space: quantize::DiffusionSpace::Oklab,
+            },
+        );
+        assert!((ordered - gray.to_oklab().l).abs() < 0.02);
+        assert!(white_share(gray, quantize::Quantization::default()).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn ordered_dithering_keeps_flat_palette_colors() {
+        let gray = rgb::sRGB::new(128, 128, 128);
+        let palette = [
+            gray,
+            rgb::sRGB::new(0, 0, 0),
+            rgb::sRGB::new(255, 255, 255),
+            // Warm and cool, about as light as the gray
+            rgb::sRGB::new(160, 120, 90),
+            rgb::sRGB::new(90, 130, 170),
+        ];
+        let canvas = render::Canvas::new(8, 8, gray);
+        for space in [
+            quantize::DiffusionSpace::LinearLight,
+            quantize::DiffusionSpace::Oklab,
+        ] {
+            let indexed = quantize::Quantization {
+                dithering: quantize::Dithering::Ordered(0.3),
+                space,
+            }
+            .quantize(&canvas, &palette);
+            assert!(indexed.indices.iter().all(|&index| index == 0));
+        }
+    }
+
+    #[test]
+    fn threshold_map_uses_every_level() {
+        let mut thresholds = itertools::iproduct!(0..8, 0..8)
+            .map(|(x, y)| quantize::threshold(x, y))
+            .collect::<Vec<_>>();
+        thresholds.sort_by(f64::total_cmp);
+        for (index, threshold) in thresholds.into_iter().enumerate() {
+            assert!(
+                (threshold - (f64::from(u8::try_from(index).unwrap()) + 0.5) / 64.0 + 0.5).abs()
+                    < 1e-12
+            );
+        }
+    }
+
+    #[test]
+    fn indexed_png_round_trip() {
+        let canvas = render::Canvas::new(4, 4, rgb::sRGB::new(200, 100, 50));
+        let indexed = quantize::Quantization {
+            dithering: quantize::Dithering::FloydSteinberg,
+            ..Default::default()
+        }
+        .quantize(
+            &canvas,
+            &[rgb::sRGB::new(255, 128, 0), rgb::sRGB::new(128, 64, 32)],
+        );
+        let path = std::env::temp_dir().join("contrasting_colors_indexed.png");
+        indexed.write_png(&path).unwrap();
+        let (read, _) = render::Canvas::read_png(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(read, indexed.to_canvas());
+    }
+}
@@ -0,0 +1,333 @@
+#![allow(dead_code)]
+
+mod font;
+
+use crate::contrast::apca;
+use crate::oklab::{Oklab, companion};
+use crate::rgb;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+const MARGIN: usize = 16;
+const GAP: usize = 8;
+const PADDING: usize = 6;
+const TILE_WIDTH: usize = 144;
+const TILE_HEIGHT: usize = 96;
+const TWIN_HEIGHT: usize = 40;
+const TILES_PER_ROW: usize = 8;
+const CELL: usize = 44;
+const BACKGROUND: rgb::sRGB = rgb::sRGB::new(32, 32, 32);
+
+/// Plain RGB pixel buffer, with just enough drawing operations for swatch sheets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<rgb::sRGB>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, background: rgb::sRGB) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> rgb::sRGB {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Row by row, from the top-left corner.
+    pub fn pixels(&self) -> &[rgb::sRGB] {
+        &self.pixels
+    }
+
+    /// Anything outside of the canvas is silently clipped.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: rgb::sRGB) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                self.pixels[row * self.width + column] = color;
+            }
+        }
+    }
+
+    /// Draws `text` with the top-left corner at `(x, y)`, each font pixel being `scale` pixels wide.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, scale: usize, color: rgb::sRGB) {
+        for (index, character) in text.chars().enumerate() {
+            let left = x + index * (font::WIDTH + 1) * scale;
+            for (row, bits) in font::glyph(character).iter().enumerate() {
+                for column in 0..font::WIDTH {
+                    if bits & (1 << (font::WIDTH - 1 - column)) != 0 {
+                        self.fill_rect(left + column * scale, y + row * scale, scale, scale, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Width in pixels of `text` as drawn by `draw_text`.
+    pub const fn text_width(text: &str, scale: usize) -> usize {
+        text.len() * (font::WIDTH + 1) * scale
+    }
+
+    /// # Errors
+    /// Returns any error from creating the file or encoding the PNG.
+    pub fn write_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(file),
+            u32::try_from(self.width).expect("Canvas too wide for a PNG"),
+            u32::try_from(self.height).expect("Canvas too tall for a PNG"),
+        );
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let data = self
+            .pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect::<Vec<u8>>();
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Reads the first frame of any 8 or 16-bit PNG, along with the alpha of each pixel,
+    /// which is `255` everywhere if the PNG doesn't have any.
+    ///
+    /// # Errors
+    /// Returns any error from opening the file or decoding the PNG.
+    pub fn read_png(path: impl AsRef<Path>) -> io::Result<(Self, Vec<u8>)> {
+        let file = std::fs::File::open(path)?;
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut data = vec![
+            0;
+            reader.output_buffer_size().ok_or_else(|| io::Error::new(
+                io::ErrorKind::InvalidData,
+                "PNG too large"
+            ))?
+        ];
+        let info = reader.next_frame(&mut data)?;
+        let data = &data[..info.buffer_size()];
+
+        let (pixels, alpha) = match info.color_type {
+            png::ColorType::Grayscale => data
+                .iter()
+                .map(|&value| (rgb::sRGB::new(value, value, value), u8::MAX))
+                .unzip(),
+            png::ColorType::GrayscaleAlpha => data
+                .chunks_exact(2)
+                .map(|pixel| (rgb::sRGB::new(pixel[0], pixel[0], pixel[0]), pixel[1]))
+                .unzip(),
+            png::ColorType::Rgb => data
+                .chunks_exact(3)
+                .map(|pixel| (rgb::sRGB::new(pixel[0], pixel[1], pixel[2]), u8::MAX))
+                .unzip(),
+            png::ColorType::Rgba => data
+                .chunks_exact(4)
+                .map(|pixel| (rgb::sRGB::new(pixel[0], pixel[1], pixel[2]), pixel[3]))
+                .unzip(),
+            // Expanded to RGB by `normalize_to_color8`
+            png::ColorType::Indexed => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "PNG palette wasn't expanded",
+                ));
+            }
+        };
+        Ok((
+            Self {
+                width: info.width as usize,
+                height: info.height as usize,
+                pixels,
+            },
+            alpha,
+        ))
+    }
+}
+
+/// The darker companion color that `main.rs` prints next to each palette entry, at `companion::Target::DARK_TWIN`.
+pub fn dark_twin(color: rgb::sRGB) -> rgb::sRGB {
+    companion::Target::DARK_TWIN.derive(color).color
+}
+
+/// Renders a palette as a sheet of labelled tiles, each with its dark twin underneath,
+/// followed by a heatmap of the pairwise `delta_E_Hyab` between all colors.
+pub fn swatch_sheet(palette: &[rgb::sRGB]) -> Canvas {
+    let twins = palette
+        .iter()
+        .map(|&color| dark_twin(color))
+        .collect::<Vec<_>>();
+    draw_sheet(palette, &twins)
+}
+
+/// # Errors
+/// Returns any error from writing the PNG.
+pub fn write_swatch_sheet(palette: &[rgb::sRGB], path: impl AsRef<Path>) -> io::Result<()> {
+    swatch_sheet(palette).write_png(path)
+}
+
+fn draw_sheet(palette: &[rgb::sRGB], twins: &[rgb::sRGB]) -> Canvas {
+    let columns = palette.len().clamp(1, TILES_PER_ROW);
+    let rows = palette.len().div_ceil(TILES_PER_ROW);
+    let swatch_height = TILE_HEIGHT + TWIN_HEIGHT;
+
+    let tiles_width = columns * (TILE_WIDTH + GAP) - GAP;
+    let heatmap_size = (palette.len() + 1) * CELL;
+    let heatmap_top = MARGIN + rows * (swatch_height + GAP) + MARGIN;
+
+    let mut canvas = Canvas::new(
+        2 * MARGIN + tiles_width.max(heatmap_size),
+        heatmap_top + heatmap_size + MARGIN,
+        BACKGROUND,
+    );
+
+    for (index, (&color, &twin)) in palette.iter().zip(twins).enumerate() {
+        let x = MARGIN + (index % TILES_PER_ROW) * (TILE_WIDTH + GAP);
+        let y = MARGIN + (index / TILES_PER_ROW) * (swatch_height + GAP);
+        draw_tile(&mut canvas, x, y, color, twin);
+    }
+
+    draw_heatmap(&mut canvas, MARGIN, heatmap_top, palette);
+    canvas
+}
+
+fn draw_tile(canvas: &mut Canvas, x: usize, y: usize, color: rgb::sRGB, twin: rgb::sRGB) {
+    let oklch = color.to_oklch();
+    let text_color = apca::best_text_color(color);
+    canvas.fill_rect(x, y, TILE_WIDTH, TILE_HEIGHT, color);
+    canvas.draw_text(x + PADDING, y + PADDING, &color.to_hex(), 2, text_color);
+    canvas.draw_text(
+        x + PADDING,
+        y + PADDING + 3 * font::HEIGHT,
+        &format!("L {:.3}", oklch.l),
+        1,
+        text_color,
+    );
+    canvas.draw_text(
+        x + PADDING,
+        y + PADDING + 5 * font::HEIGHT,
+        &format!("C {:.3}", oklch.c),
+        1,
+        text_color,
+    );
+    canvas.draw_text(
+        x + PADDING,
+        y + PADDING + 7 * font::HEIGHT,
+        &format!("H {:.1}", oklch.h.degrees()),
+        1,
+        text_color,
+    );
+
+    let twin_text_color = apca::best_text_color(twin);
+    canvas.fill_rect(x, y + TILE_HEIGHT, TILE_WIDTH, TWIN_HEIGHT, twin);
+    canvas.draw_text(
+        x + PADDING,
+        y + TILE_HEIGHT + PADDING,
+        &twin.to_hex(),
+        1,
+        twin_text_color,
+    );
+}
+
+/// Lighter cells are farther apart, scaled so that the largest difference in the palette is white.
+fn draw_heatmap(canvas: &mut Canvas, x: usize, y: usize, palette: &[rgb::sRGB]) {
+    let oklab_palette = palette
+        .iter()
+        .map(|color| color.to_oklab())
+        .collect::<Vec<_>>();
+    let max_delta = oklab_palette
+        .iter()
+        .flat_map(|first| {
+            oklab_palette
+                .iter()
+                .map(|second| first.delta_E_Hyab(*second))
+        })
+        .fold(f64::EPSILON, f64::max);
+
+    for (index, &color) in palette.iter().enumerate() {
+        let offset = (index + 1) * CELL;
+        canvas.fill_rect(x + offset + 2, y + 2, CELL - 4, CELL - 4, color);
+        canvas.fill_rect(x + 2, y + offset + 2, CELL - 4, CELL - 4, color);
+    }
+
+    for (row, first) in oklab_palette.iter().enumerate() {
+        for (column, second) in oklab_palette.iter().enumerate() {
+            if row == column {
+                continue;
+            }
+            let delta = first.delta_E_Hyab(*second);
+            let cell_color = Oklab {
+                l: delta / max_delta,
+                ..Oklab::BLACK
+            }
+            .to_srgb();
+            let text = format!("{delta:.3}");
+
+            let left = x + (column + 1) * CELL;
+            let top = y + (row + 1) * CELL;
+            canvas.fill_rect(left, top, CELL, CELL, cell_color);
+            canvas.draw_text(
+                left + (CELL - Canvas::text_width(&text, 1)) / 2,
+                top + (CELL - font::HEIGHT) / 2,
+                &text,
+                1,
+                apca::best_text_color(cell_color),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render;
+    use crate::rgb;
+
+    #[test]
+    fn text_is_drawn() {
+        let mut canvas = render::Canvas::new(16, 16, rgb::sRGB::default());
+        canvas.draw_text(0, 0, "1", 2, rgb::sRGB::new(255, 255, 255));
+        // Top of the "1" stem, and the blank pixel to the left of it
+        assert_eq!(canvas.pixel(4, 0), rgb::sRGB::new(255, 255, 255));
+        assert_eq!(canvas.pixel(0, 0), rgb::sRGB::default());
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let mut canvas = render::Canvas::new(3, 2, rgb::sRGB::new(255, 0, 0));
+        canvas.fill_rect(1, 1, 2, 1, rgb::sRGB::new(0, 64, 255));
+        let path = std::env::temp_dir().join("contrasting_colors_round_trip.png");
+        canvas.write_png(&path).unwrap();
+        let (read, alpha) = render::Canvas::read_png(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(read, canvas);
+        assert_eq!(alpha, [u8::MAX; 6]);
+    }
+
+    #[test]
+    fn sheet_contains_palette() {
+        let palette = [rgb::sRGB::new(255, 0, 0), rgb::sRGB::new(0, 0, 255)];
+        let twins = [rgb::sRGB::new(128, 64, 64), rgb::sRGB::new(64, 79, 128)];
+        let canvas = render::draw_sheet(&palette, &twins);
+
+        // Bottom-right corners of each tile stay clear of any label text
+        let first = (
+            render::MARGIN + render::TILE_WIDTH - 1,
+            render::MARGIN + render::TILE_HEIGHT - 1,
+        );
+        assert_eq!(canvas.pixel(first.0, first.1), palette[0]);
+        assert_eq!(
+            canvas.pixel(first.0, first.1 + render::TWIN_HEIGHT),
+            twins[0]
+        );
+        assert_eq!(
+            canvas.pixel(first.0 + render::TILE_WIDTH + render::GAP, first.1),
+            palette[1]
+        );
+    }
+}
@@ -1,9 +1,14 @@
 #![allow(dead_code)]
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub mod named;
+
+use crate::oklab::Metric;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 /// Implementation of RGB colors from the `rgb` crate, modified for personal use.
 ///
 /// Standard RGB color.
+/// Ordered by red, then green, then blue, which is only meant for breaking ties consistently.
 #[allow(non_camel_case_types)]
 pub struct sRGB {
     pub r: u8,
@@ -33,6 +38,29 @@ impl std::fmt::Display for lRGB {
     }
 }
 
+/// A string that is neither `sRGB::from_hex` notation nor a CSS color name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseColorError(pub String);
+
+impl std::fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` isn't a hex color or a CSS color name", self.0)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl std::str::FromStr for sRGB {
+    type Err = ParseColorError;
+
+    /// Hex notation like `#6200FF`, or a CSS color name like `blueviolet`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(text)
+            .or_else(|| Self::from_name(text))
+            .ok_or_else(|| ParseColorError(text.to_string()))
+    }
+}
+
 impl Default for sRGB {
     /// Default to pure black: `(0, 0, 0)`.
     fn default() -> Self {
@@ -68,6 +96,43 @@ impl sRGB {
         }
     }
 
+    /// Uppercase hex notation: `#6200FF`.
+    pub fn to_hex(self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+    /// Parses `to_hex` notation, with or without the `#` and in either case.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if digits.len() != 6 || !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |index: usize| u8::from_str_radix(&digits[index..index + 2], 16).ok();
+        Some(Self {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        })
+    }
+
+    /// CSS named color, in any case: `RebeccaPurple`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        named::NAMED_COLORS
+            .binary_search_by(|&(other, _)| other.cmp(&name))
+            .ok()
+            .map(|index| named::NAMED_COLORS[index].1)
+    }
+    /// The CSS named color closest to this one under `metric`, along with the distance.
+    /// Ties go to the first name alphabetically, such as `aqua` over `cyan`.
+    pub fn nearest_named(self, metric: Metric) -> (&'static str, f64) {
+        let oklab = self.to_oklab();
+        named::NAMED_COLORS
+            .iter()
+            .map(|&(name, color)| (name, metric.delta(oklab, color.to_oklab())))
+            .reduce(|best, named| if named.1 < best.1 { named } else { best })
+            .expect("There are named colors")
+    }
+
     pub fn min(self) -> u8 {
         self.r.min(self.g).min(self.b)
     }
@@ -79,6 +144,14 @@ impl sRGB {
         // itertools calls this the cartesian product: (0,0,0),(0,0,1),...(0,0,255),(0,1,0),...(255,255,254),(255,255,255)
         itertools::iproduct!(0..=255, 0..=255, 0..=255).map(|(r, g, b)| Self { r, g, b })
     }
+    /// The 8 one-bit colors: black, white, and the primary and secondary colors.
+    pub fn corners() -> impl Iterator<Item = Self> + Clone + Send {
+        itertools::iproduct!([0, 255], [0, 255], [0, 255]).map(|(r, g, b)| Self { r, g, b })
+    }
+    /// All 256 neutral colors, from black to white.
+    pub fn grays() -> impl Iterator<Item = Self> + Clone + Send {
+        (0..=255).map(|value| Self::new(value, value, value))
+    }
 }
 
 impl lRGB {
@@ -94,6 +167,39 @@ impl lRGB {
         }
     }
 
+    /// The sRGB colors around this one, with each channel rounded both down and up,
+    /// for when plain rounding isn't the closest by some other measure.
+    #[allow(clippy::cast_possible_truncation)] // allows f64 to u8
+    #[allow(clippy::cast_sign_loss)] // also allows f64 to u8, ignoring the sign
+    pub fn srgb_neighbors(self) -> impl Iterator<Item = sRGB> + Clone {
+        let around = |channel: f64| {
+            let value = (255.0 * gamma(channel)).clamp(0.0, 255.0);
+            [value.floor() as u8, value.ceil() as u8]
+        };
+        itertools::iproduct!(around(self.r), around(self.g), around(self.b)).map(|(r, g, b)| sRGB {
+            r,
+            g,
+            b,
+        })
+    }
+
+    /// WCAG 2.x relative luminance, the Y of CIE XYZ with the Rec. 709 coefficients.
+    ///
+    /// WCAG linearizes with a threshold of `0.039_28` instead of `0.040_45`, which makes no difference for 8-bit input, as no 8-bit value falls between the two.
+    pub fn relative_luminance(self) -> f64 {
+        self.r
+            .mul_add(0.2126, self.g.mul_add(0.7152, 0.0722 * self.b))
+    }
+
+    /// Plain clipping to the sRGB gamut.
+    pub const fn clamp(self) -> Self {
+        Self {
+            r: self.r.clamp(0.0, 1.0),
+            g: self.g.clamp(0.0, 1.0),
+            b: self.b.clamp(0.0, 1.0),
+        }
+    }
+
     pub const fn min(self) -> f64 {
         self.r.min(self.g).min(self.b)
     }
@@ -123,6 +229,7 @@ fn gamma(u: f64) -> f64 {
 
 #[cfg(test)]
 mod tests {
+    use crate::oklab;
     use crate::rgb;
 
     #[test]
@@ -148,4 +255,30 @@ mod tests {
             .to_srgb()
         );
     }
+
+    #[test]
+    fn hex_round_trip() {
+        let color = rgb::sRGB::new(98, 0, 255);
+        assert_eq!(color.to_hex(), "#6200FF");
+        assert_eq!(rgb::sRGB::from_hex("#6200FF"), Some(color));
+        assert_eq!(rgb::sRGB::from_hex("6200ff"), Some(color));
+        assert_eq!(rgb::sRGB::from_hex("#6200F"), None);
+        assert_eq!(rgb::sRGB::from_hex("#6200FG"), None);
+        assert_eq!(rgb::sRGB::from_hex("#+1+2+3"), None);
+    }
+
+    #[test]
+    fn named_colors() {
+        assert!(rgb::named::NAMED_COLORS.is_sorted_by_key(|&(name, _)| name));
+        assert_eq!("RebeccaPurple".parse(), Ok(rgb::sRGB::new(102, 51, 153)));
+        assert_eq!("#6200FF".parse(), Ok(rgb::sRGB::new(98, 0, 255)));
+        assert!("notacolor".parse::<rgb::sRGB>().is_err());
+
+        let (name, delta) = rgb::sRGB::new(0, 255, 255).nearest_named(oklab::Metric::Ab);
+        assert_eq!(name, "aqua");
+        assert!(delta.abs() < 1e-12);
+        // The first color of the palette search
+        let (name, _) = rgb::sRGB::new(98, 0, 255).nearest_named(oklab::Metric::Hyab);
+        assert_eq!(name, "blueviolet");
+    }
 }
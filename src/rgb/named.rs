@@ -0,0 +1,156 @@
+//! CSS named colors, which are the X11 colors with a few changes, such as `gray`, `green` and `maroon` being darker.
+
+use super::sRGB;
+
+/// Every CSS named color in alphabetical order, so they can be binary searched.
+/// Includes both spellings of gray and the aliases `aqua`/`cyan` and `fuchsia`/`magenta`.
+pub const NAMED_COLORS: [(&str, sRGB); 148] = [
+    ("aliceblue", sRGB::new(240, 248, 255)),
+    ("antiquewhite", sRGB::new(250, 235, 215)),
+    ("aqua", sRGB::new(0, 255, 255)),
+    ("aquamarine", sRGB::new(127, 255, 212)),
+    ("azure", sRGB::new(240, 255, 255)),
+    ("beige", sRGB::new(245, 245, 220)),
+    ("bisque", sRGB::new(255, 228, 196)),
+    ("black", sRGB::new(0, 0, 0)),
+    ("blanchedalmond", sRGB::new(255, 235, 205)),
+    ("blue", sRGB::new(0, 0, 255)),
+    ("blueviolet", sRGB::new(138, 43, 226)),
+    ("brown", sRGB::new(165, 42, 42)),
+    ("burlywood", sRGB::new(222, 184, 135)),
+    ("cadetblue", sRGB::new(95, 158, 160)),
+    ("chartreuse", sRGB::new(127, 255, 0)),
+    ("chocolate", sRGB::new(210, 105, 30)),
+    ("coral", sRGB::new(255, 127, 80)),
+    ("cornflowerblue", sRGB::new(100, 149, 237)),
+    ("cornsilk", sRGB::new(255, 248, 220)),
+    ("crimson", sRGB::new(220, 20, 60)),
+    ("cyan", sRGB::new(0, 255, 255)),
+    ("darkblue", sRGB::new(0, 0, 139)),
+    ("darkcyan", sRGB::new(0, 139, 139)),
+    ("darkgoldenrod", sRGB::new(184, 134, 11)),
+    ("darkgray", sRGB::new(169, 169, 169)),
+    ("darkgreen", sRGB::new(0, 100, 0)),
+    ("darkgrey", sRGB::new(169, 169, 169)),
+    ("darkkhaki", sRGB::new(189, 183, 107)),
+    ("darkmagenta", sRGB::new(139, 0, 139)),
+    ("darkolivegreen", sRGB::new(85, 107, 47)),
+    ("darkorange", sRGB::new(255, 140, 0)),
+    ("darkorchid", sRGB::new(153, 50, 204)),
+    ("darkred", sRGB::new(139, 0, 0)),
+    ("darksalmon", sRGB::new(233, 150, 122)),
+    ("darkseagreen", sRGB::new(143, 188, 143)),
+    ("darkslateblue", sRGB::new(72, 61, 139)),
+    ("darkslategray", sRGB::new(47, 79, 79)),
+    ("darkslategrey", sRGB::new(47, 79, 79)),
+    ("darkturquoise", sRGB::new(0, 206, 209)),
+    ("darkviolet", sRGB::new(148, 0, 211)),
+    ("deeppink", sRGB::new(255, 20, 147)),
+    ("deepskyblue", sRGB::new(0, 191, 255)),
+    ("dimgray", sRGB::new(105, 105, 105)),
+    ("dimgrey", sRGB::new(105, 105, 105)),
+    ("dodgerblue", sRGB::new(30, 144, 255)),
+    ("firebrick", sRGB::new(178, 34, 34)),
+    ("floralwhite", sRGB::new(255, 250, 240)),
+    ("forestgreen", sRGB::new(34, 139, 34)),
+    ("fuchsia", sRGB::new(255, 0, 255)),
+    ("gainsboro", sRGB::new(220, 220, 220)),
+    ("ghostwhite", sRGB::new(248, 248, 255)),
+    ("gold", sRGB::new(255, 215, 0)),
+    ("goldenrod", sRGB::new(218, 165, 32)),
+    ("gray", sRGB::new(128, 128, 128)),
+    ("green", sRGB::new(0, 128, 0)),
+    ("greenyellow", sRGB::new(173, 255, 47)),
+    ("grey", sRGB::new(128, 128, 128)),
+    ("honeydew", sRGB::new(240, 255, 240)),
+    ("hotpink", sRGB::new(255, 105, 180)),
+    ("indianred", sRGB::new(205, 92, 92)),
+    ("indigo", sRGB::new(75, 0, 130)),
+    ("ivory", sRGB::new(255, 255, 240)),
+    ("khaki", sRGB::new(240, 230, 140)),
+    ("lavender", sRGB::new(230, 230, 250)),
+    ("lavenderblush", sRGB::new(255, 240, 245)),
+    ("lawngreen", sRGB::new(124, 252, 0)),
+    ("lemonchiffon", sRGB::new(255, 250, 205)),
+    ("lightblue", sRGB::new(173, 216, 230)),
+    ("lightcoral", sRGB::new(240, 128, 128)),
+    ("lightcyan", sRGB::new(224, 255, 255)),
+    ("lightgoldenrodyellow", sRGB::new(250, 250, 210)),
+    ("lightgray", sRGB::new(211, 211, 211)),
+    ("lightgreen", sRGB::new(144, 238, 144)),
+    ("lightgrey", sRGB::new(211, 211, 211)),
+    ("lightpink", sRGB::new(255, 182, 193)),
+    ("lightsalmon", sRGB::new(255, 160, 122)),
+    ("lightseagreen", sRGB::new(32, 178, 170)),
+    ("lightskyblue", sRGB::new(135, 206, 250)),
+    ("lightslategray", sRGB::new(119, 136, 153)),
+    ("lightslategrey", sRGB::new(119, 136, 153)),
+    ("lightsteelblue", sRGB::new(176, 196, 222)),
+    ("lightyellow", sRGB::new(255, 255, 224)),
+    ("lime", sRGB::new(0, 255, 0)),
+    ("limegreen", sRGB::new(50, 205, 50)),
+    ("linen", sRGB::new(250, 240, 230)),
+    ("magenta", sRGB::new(255, 0, 255)),
+    ("maroon", sRGB::new(128, 0, 0)),
+    ("mediumaquamarine", sRGB::new(102, 205, 170)),
+    ("mediumblue", sRGB::new(0, 0, 205)),
+    ("mediumorchid", sRGB::new(186, 85, 211)),
+    ("mediumpurple", sRGB::new(147, 112, 219)),
+    ("mediumseagreen", sRGB::new(60, 179, 113)),
+    ("mediumslateblue", sRGB::new(123, 104, 238)),
+    ("mediumspringgreen", sRGB::new(0, 250, 154)),
+    ("mediumturquoise", sRGB::new(72, 209, 204)),
+    ("mediumvioletred", sRGB::new(199, 21, 133)),
+    ("midnightblue", sRGB::new(25, 25, 112)),
+    ("mintcream", sRGB::new(245, 255, 250)),
+    ("mistyrose", sRGB::new(255, 228, 225)),
+    ("moccasin", sRGB::new(255, 228, 181)),
+    ("navajowhite", sRGB::new(255, 222, 173)),
+    ("navy", sRGB::new(0, 0, 128)),
+    ("oldlace", sRGB::new(253, 245, 230)),
+    ("olive", sRGB::new(128, 128, 0)),
+    ("olivedrab", sRGB::new(107, 142, 35)),
+    ("orange", sRGB::new(255, 165, 0)),
+    ("orangered", sRGB::new(255, 69, 0)),
+    ("orchid", sRGB::new(218, 112, 214)),
+    ("palegoldenrod", sRGB::new(238, 232, 170)),
+    ("palegreen", sRGB::new(152, 251, 152)),
+    ("paleturquoise", sRGB::new(175, 238, 238)),
+    ("palevioletred", sRGB::new(219, 112, 147)),
+    ("papayawhip", sRGB::new(255, 239, 213)),
+    ("peachpuff", sRGB::new(255, 218, 185)),
+    ("peru", sRGB::new(205, 133, 63)),
+    ("pink", sRGB::new(255, 192, 203)),
+    ("plum", sRGB::new(221, 160, 221)),
+    ("powderblue", sRGB::new(176, 224, 230)),
+    ("purple", sRGB::new(128, 0, 128)),
+    ("rebeccapurple", sRGB::new(102, 51, 153)),
+    ("red", sRGB::new(255, 0, 0)),
+    ("rosybrown", sRGB::new(188, 143, 143)),
+    ("royalblue", sRGB::new(65, 105, 225)),
+    ("saddlebrown", sRGB::new(139, 69, 19)),
+    ("salmon", sRGB::new(250, 128, 114)),
+    ("sandybrown", sRGB::new(244, 164, 96)),
+    ("seagreen", sRGB::new(46, 139, 87)),
+    ("seashell", sRGB::new(255, 245, 238)),
+    ("sienna", sRGB::new(160, 82, 45)),
+    ("silver", sRGB::new(192, 192, 192)),
+    ("skyblue", sRGB::new(135, 206, 235)),
+    ("slateblue", sRGB::new(106, 90, 205)),
+    ("slategray", sRGB::new(112, 128, 144)),
+    ("slategrey", sRGB::new(112, 128, 144)),
+    ("snow", sRGB::new(255, 250, 250)),
+    ("springgreen", sRGB::new(0, 255, 127)),
+    ("steelblue", sRGB::new(70, 130, 180)),
+    ("tan", sRGB::new(210, 180, 140)),
+    ("teal", sRGB::new(0, 128, 128)),
+    ("thistle", sRGB::new(216, 191, 216)),
+    ("tomato", sRGB::new(255, 99, 71)),
+    ("turquoise", sRGB::new(64, 224, 208)),
+    ("violet", sRGB::new(238, 130, 238)),
+    ("wheat", sRGB::new(245, 222, 179)),
+    ("white", sRGB::new(255, 255, 255)),
+    ("whitesmoke", sRGB::new(245, 245, 245)),
+    ("yellow", sRGB::new(255, 255, 0)),
+    ("yellowgreen", sRGB::new(154, 205, 50)),
+];
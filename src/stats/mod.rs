@@ -0,0 +1,251 @@
+#![allow(dead_code)]
+
+use crate::oklab::{Hue, Oklab, Oklch, SaturationModel};
+use crate::rgb::sRGB;
+use rayon::prelude::*;
+
+/// A single number describing a color, to take statistics of.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Channel {
+    /// `Oklab::l`
+    Lightness,
+    /// `Oklab::chroma`
+    Chroma,
+    /// `Oklab::hue` in degrees, leaving out grays below `Oklch::ACHROMATIC`, whose hue is meaningless.
+    Hue,
+    /// `Oklab::saturation`, which is `0` for grays.
+    Saturation(SaturationModel),
+}
+
+impl Channel {
+    /// `None` if the channel doesn't apply to `color`, which is only the hue of grays.
+    pub fn value(self, color: Oklab) -> Option<f64> {
+        match self {
+            Self::Lightness => Some(color.l),
+            Self::Chroma => Some(color.chroma()),
+            Self::Hue => (color.chroma() >= Oklch::ACHROMATIC).then(|| color.hue().degrees()),
+            Self::Saturation(model) => Some(color.saturation(model)),
+        }
+    }
+}
+
+/// Counts of values in equally wide bins from `minimum` to `maximum`, the last bin including `maximum` itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub minimum: f64,
+    pub maximum: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn bin_width(&self) -> f64 {
+        (self.maximum - self.minimum) / bin_count(self.counts.len())
+    }
+
+    /// Index of the bin `value` falls in, `None` if outside of the range.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // clamped to the bins
+    pub fn bin(&self, value: f64) -> Option<usize> {
+        if !(self.minimum..=self.maximum).contains(&value) || self.counts.is_empty() {
+            return None;
+        }
+        let bin = ((value - self.minimum) / self.bin_width()).floor();
+        Some((bin.max(0.0) as usize).min(self.counts.len() - 1))
+    }
+
+    /// Range of values covered by bin `index`.
+    pub fn bounds(&self, index: usize) -> (f64, f64) {
+        let width = self.bin_width();
+        (
+            width.mul_add(bin_count(index), self.minimum),
+            width.mul_add(bin_count(index + 1), self.minimum),
+        )
+    }
+}
+
+#[allow(clippy::cast_precision_loss)] // bin counts are small
+const fn bin_count(count: usize) -> f64 {
+    count as f64
+}
+
+/// Every value of a channel over a set of colors, sorted so that quantiles are cheap.
+/// Colors the channel doesn't apply to, such as grays for `Channel::Hue`, are left out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    pub channel: Channel,
+    sorted: Vec<f64>,
+}
+
+impl Distribution {
+    /// Takes 128 MB for the whole sRGB cube.
+    pub fn new(channel: Channel, candidates: impl Iterator<Item = sRGB> + Send) -> Self {
+        let mut sorted = candidates
+            .par_bridge()
+            .filter_map(|color| channel.value(color.to_oklab()))
+            .collect::<Vec<_>>();
+        sorted.par_sort_unstable_by(f64::total_cmp);
+        Self { channel, sorted }
+    }
+
+    /// Over the whole sRGB cube.
+    pub fn cube(channel: Channel) -> Self {
+        Self::new(channel, sRGB::all_colors())
+    }
+
+    pub const fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    pub fn minimum(&self) -> Option<f64> {
+        self.sorted.first().copied()
+    }
+
+    pub fn maximum(&self) -> Option<f64> {
+        self.sorted.last().copied()
+    }
+
+    /// Quantile `p` from `0.0` to `1.0`, using the inverse of the empirical distribution function
+    /// and averaging at discontinuities (type 2 in R and `peroxide`), so the median of an even count is the usual one.
+    /// `None` if there are no values.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )] // at most 2^24 values
+    pub fn quantile(&self, p: f64) -> Option<f64> {
+        let count = self.sorted.len();
+        if count == 0 {
+            return None;
+        }
+        let position = p.clamp(0.0, 1.0) * count as f64;
+        let index = position.floor() as usize;
+        if position > position.floor() || index == 0 {
+            Some(self.sorted[index.min(count - 1)])
+        } else if index == count {
+            self.maximum()
+        } else {
+            Some(f64::midpoint(self.sorted[index - 1], self.sorted[index]))
+        }
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+
+    /// Arithmetic mean, except for `Channel::Hue` where it's the circular mean, `None` if it doesn't exist.
+    #[allow(clippy::cast_precision_loss)] // at most 2^24 values
+    pub fn mean(&self) -> Option<f64> {
+        if self.sorted.is_empty() {
+            return None;
+        }
+        if self.channel == Channel::Hue {
+            let (sin, cos) = self
+                .sorted
+                .par_iter()
+                .map(|&degrees| Hue::from_degrees(degrees).radians().sin_cos())
+                .reduce(
+                    || (0.0, 0.0),
+                    |first, second| (first.0 + second.0, first.1 + second.1),
+                );
+            return (sin.hypot(cos) > f64::EPSILON)
+                .then(|| Hue::from_radians(sin.atan2(cos)).degrees());
+        }
+        Some(self.sorted.par_iter().sum::<f64>() / self.sorted.len() as f64)
+    }
+
+    /// `bins` equally wide bins from the minimum to the maximum.
+    pub fn histogram(&self, bins: usize) -> Histogram {
+        self.histogram_over(
+            bins,
+            self.minimum().unwrap_or_default(),
+            self.maximum().unwrap_or_default(),
+        )
+    }
+
+    /// `bins` equally wide bins from `minimum` to `maximum`, leaving out any value outside of them.
+    pub fn histogram_over(&self, bins: usize, minimum: f64, maximum: f64) -> Histogram {
+        let mut histogram = Histogram {
+            minimum,
+            maximum,
+            counts: vec![0; bins],
+        };
+        for &value in &self.sorted {
+            if let Some(bin) = histogram.bin(value) {
+                histogram.counts[bin] += 1;
+            }
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oklab;
+    use crate::rgb;
+    use crate::stats;
+
+    const DIFFERENCE: f64 = 1e-12;
+
+    #[test]
+    fn quantiles_average_at_discontinuities() {
+        let grays = stats::Distribution::new(stats::Channel::Lightness, rgb::sRGB::grays());
+        let lightness = |value| rgb::sRGB::new(value, value, value).to_oklab().l;
+
+        assert_eq!(grays.len(), 256);
+        assert_eq!(grays.quantile(0.0), Some(lightness(0)));
+        assert_eq!(grays.quantile(1.0), Some(lightness(255)));
+        let median = f64::midpoint(lightness(127), lightness(128));
+        assert!((grays.median().unwrap() - median).abs() < DIFFERENCE);
+        // 256 / 3 isn't a whole number, so no averaging
+        assert_eq!(grays.quantile(1.0 / 3.0), Some(lightness(85)));
+        assert!(
+            stats::Distribution::new(stats::Channel::Lightness, std::iter::empty())
+                .median()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn hue_mean_is_circular() {
+        // Red is around 29° and magenta around 328°, so the arithmetic mean would be close to 180°
+        let (red, magenta) = (rgb::sRGB::new(255, 0, 0), rgb::sRGB::new(255, 0, 255));
+        let hues = stats::Distribution::new(stats::Channel::Hue, [red, magenta].into_iter());
+        let expected = oklab::Hue::from_degrees(f64::midpoint(
+            red.to_oklch().h.degrees(),
+            magenta.to_oklch().h.degrees() - 360.0,
+        ));
+        let mean = oklab::Hue::from_degrees(hues.mean().unwrap());
+        assert!(mean.difference(expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hue_leaves_out_grays() {
+        let yellow = rgb::sRGB::new(255, 255, 0);
+        let hues = stats::Distribution::new(
+            stats::Channel::Hue,
+            rgb::sRGB::grays().chain(std::iter::once(yellow)),
+        );
+        assert_eq!(hues.len(), 1);
+        let mean = oklab::Hue::from_degrees(hues.mean().unwrap());
+        assert!(mean.difference(yellow.to_oklch().h).abs() < 1e-9);
+    }
+
+    #[test]
+    fn histogram_counts_everything() {
+        let grays = stats::Distribution::new(stats::Channel::Lightness, rgb::sRGB::grays());
+        let histogram = grays.histogram(10);
+        assert_eq!(histogram.counts.iter().sum::<usize>(), 256);
+        assert_eq!(histogram.bin(histogram.maximum), Some(9));
+        assert_eq!(histogram.bin(1.5), None);
+        assert!((histogram.bounds(9).1 - histogram.maximum).abs() < DIFFERENCE);
+
+        let saturations = stats::Distribution::new(
+            stats::Channel::Saturation(oklab::SaturationModel::Okhsl),
+            rgb::sRGB::grays(),
+        );
+        assert_eq!(saturations.maximum(), Some(0.0));
+    }
+}